@@ -1,90 +1,430 @@
+use anyhow::{Context, Result, bail, ensure};
 use aoc25::{Input, load_input};
-use itertools::Itertools;
-use rstest::rstest;
+
+const RADIX: u32 = 10;
+
+/// Count and sum of the IDs in a range that match a pattern
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+struct RepeatTotals {
+    count: u128,
+    sum: u128,
+}
+
+impl RepeatTotals {
+    fn checked_add(self, other: RepeatTotals) -> Option<RepeatTotals> {
+        Some(RepeatTotals {
+            count: self.count.checked_add(other.count)?,
+            sum: self.sum.checked_add(other.sum)?,
+        })
+    }
+}
+
+/// A property of an ID that the range scanner can search for
+trait IdPattern {
+    fn matches(&self, id: u128) -> bool;
+
+    /// The first matching ID in `from..=to`. Checks every ID unless the pattern can jump ahead.
+    fn next_match(&self, from: u128, to: u128) -> Option<u128> {
+        (from..=to).find(|&id| self.matches(id))
+    }
+
+    /// Count and sum of the matching IDs in `range`, or `None` if the sum overflows
+    fn totals(&self, range: (u128, u128)) -> Option<RepeatTotals> {
+        matches_in(range, self)
+            .into_iter()
+            .try_fold(RepeatTotals::default(), |totals, id| {
+                totals.checked_add(RepeatTotals { count: 1, sum: id })
+            })
+    }
+}
+
+/// A block of digits repeated exactly `n` times, e.g. 123123 for `n` = 2
+struct Repeats {
+    n: u32,
+    radix: u32,
+}
+
+/// A block of digits repeated at least twice
+struct AnyRepeats {
+    radix: u32,
+}
+
+/// Reads the same forwards and backwards
+struct Palindrome {
+    radix: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+enum Direction {
+    Increasing,
+    Decreasing,
+}
+
+/// Digits never decrease (or never increase) from left to right, e.g. 1123
+struct MonotoneDigits {
+    direction: Direction,
+    radix: u32,
+}
+
+/// Some `len` digit substring appears at least `copies` times without overlapping
+struct SubstringCopies {
+    len: usize,
+    copies: usize,
+    radix: u32,
+}
+
+impl IdPattern for Repeats {
+    fn matches(&self, id: u128) -> bool {
+        next_number_with_n_repeats(id, self.n, self.radix) == Some(id)
+    }
+
+    fn next_match(&self, from: u128, to: u128) -> Option<u128> {
+        next_number_with_n_repeats(from, self.n, self.radix).filter(|&id| id <= to)
+    }
+
+    fn totals(&self, range: (u128, u128)) -> Option<RepeatTotals> {
+        totals_with_n_repeats(range, self.n, self.radix)
+    }
+}
+
+impl IdPattern for AnyRepeats {
+    fn matches(&self, id: u128) -> bool {
+        self.next_match(id, id).is_some()
+    }
+
+    fn next_match(&self, from: u128, to: u128) -> Option<u128> {
+        (2..=num_digits(to, self.radix))
+            .filter_map(|n| next_number_with_n_repeats(from, n, self.radix))
+            .filter(|&id| id <= to)
+            .min()
+    }
+
+    fn totals(&self, range: (u128, u128)) -> Option<RepeatTotals> {
+        totals_with_any_repeats(range, self.radix)
+    }
+}
+
+impl IdPattern for Palindrome {
+    fn matches(&self, id: u128) -> bool {
+        let digits = digits_of(id, self.radix);
+        digits.iter().eq(digits.iter().rev())
+    }
+}
+
+impl IdPattern for MonotoneDigits {
+    fn matches(&self, id: u128) -> bool {
+        digits_of(id, self.radix)
+            .windows(2)
+            .all(|pair| match self.direction {
+                Direction::Increasing => pair[0] <= pair[1],
+                Direction::Decreasing => pair[0] >= pair[1],
+            })
+    }
+}
+
+impl IdPattern for SubstringCopies {
+    fn matches(&self, id: u128) -> bool {
+        let digits = digits_of(id, self.radix);
+        if self.len == 0 || digits.len() < self.len {
+            return false;
+        }
+        digits.windows(self.len).any(|substring| {
+            // Count non-overlapping occurrences, taking the leftmost each time
+            let mut count = 0;
+            let mut idx = 0;
+            while idx + self.len <= digits.len() {
+                if &digits[idx..idx + self.len] == substring {
+                    count += 1;
+                    idx += self.len;
+                } else {
+                    idx += 1;
+                }
+            }
+            count >= self.copies
+        })
+    }
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
     let lines = load_input(2, Input::Puzzle);
-    let a = solve_part_a(&lines[0]);
+    if let Some(spec) = args.iter().skip_while(|arg| *arg != "--pattern").nth(1) {
+        let pattern = parse_pattern(spec, RADIX).expect("Couldn't parse pattern");
+        if args.iter().any(|arg| arg == "--list") {
+            for (range, ids) in scan_ranges(&lines[0], pattern.as_ref()) {
+                println!(
+                    "For range {}-{}, found {} IDs:",
+                    range.0,
+                    range.1,
+                    ids.len()
+                );
+                ids.iter().for_each(|id| println!("  - '{id}'"));
+            }
+        }
+        let total = solve_part_b(&lines[0], pattern.as_ref());
+        println!("Sum of IDs matching {spec}: {total}");
+        return;
+    }
+    let a = solve_part_a(&lines[0], &Repeats { n: 2, radix: RADIX });
     println!("Solution to a: {a}");
-    let b = solve_part_b(&lines[0]);
+    let b = solve_part_b(&lines[0], &AnyRepeats { radix: RADIX });
     println!("Solution to b: {b}");
 }
 
-fn solve_part_a(line: &str) -> u64 {
-    line.split(",")
-        .inspect(|x| println!("For range: {x}"))
-        .map(parse_range)
-        .flat_map(|range| numbers_with_n_repeats(range, 2))
-        .inspect(|x| println!("  - '{x}'"))
-        .sum()
+fn solve_part_a(line: &str, pattern: &dyn IdPattern) -> u128 {
+    sum_matches(parse_ranges(line, RADIX), pattern)
 }
 
-fn solve_part_b(line: &str) -> u64 {
-    line.split(",")
-        .inspect(|x| println!("For range {x}, found the following repeated numbers:"))
-        .map(parse_range)
-        .flat_map(numbers_with_any_repeats)
-        .inspect(|x| println!("  - '{x}'"))
-        .unique()
+fn solve_part_b(line: &str, pattern: &dyn IdPattern) -> u128 {
+    // Merge overlaps first so that numbers in several ranges are only counted once
+    sum_matches(merge_ranges(parse_ranges(line, RADIX)), pattern)
+}
+
+fn sum_matches(ranges: Vec<(u128, u128)>, pattern: &dyn IdPattern) -> u128 {
+    ranges
+        .into_iter()
+        .inspect(|(start, end)| println!("For range: {start}-{end}"))
+        .map(|range| pattern.totals(range).expect("Sum overflowed u128"))
+        .inspect(|totals| println!("  - {} numbers, summing to {}", totals.count, totals.sum))
+        .map(|totals| totals.sum)
         .sum()
 }
 
-fn numbers_with_any_repeats(range: (u64, u64)) -> Vec<u64> {
-    let max_repeats_possible = range.1.to_string().len();
-    (2..max_repeats_possible + 1)
-        .flat_map(|n| numbers_with_n_repeats(range, n as u32))
+/// Every ID matching `pattern` in each range of the input line
+fn scan_ranges(line: &str, pattern: &dyn IdPattern) -> Vec<((u128, u128), Vec<u128>)> {
+    parse_ranges(line, RADIX)
+        .into_iter()
+        .map(|range| (range, matches_in(range, pattern)))
         .collect()
 }
 
-fn numbers_with_n_repeats(range: (u64, u64), n: u32) -> Vec<u64> {
-    let next_number = |s| next_number_with_n_repeats(s, n);
-    let mut next = next_number(range.0);
-    let mut repeats = Vec::new();
-    while next <= range.1 {
-        repeats.push(next);
-        next = next_number(next + 1);
+fn matches_in<P: IdPattern + ?Sized>(range: (u128, u128), pattern: &P) -> Vec<u128> {
+    let mut next = pattern.next_match(range.0, range.1);
+    let mut found = Vec::new();
+    while let Some(id) = next {
+        found.push(id);
+        next = id
+            .checked_add(1)
+            .filter(|&from| from <= range.1)
+            .and_then(|from| pattern.next_match(from, range.1));
     }
-    repeats
+    found
+}
+
+/// Parse a pattern given as `repeats:N`, `any-repeats`, `palindrome`, `increasing`,
+/// `decreasing` or `copies:LEN:K`
+fn parse_pattern(spec: &str, radix: u32) -> Result<Box<dyn IdPattern>> {
+    let parts: Vec<&str> = spec.split(':').collect();
+    let number = |idx: usize| -> Result<usize> {
+        parts
+            .get(idx)
+            .context("Missing pattern argument")?
+            .parse()
+            .context("Couldn't parse pattern argument")
+    };
+    Ok(match parts[0] {
+        "repeats" => {
+            let n = u32::try_from(number(1)?).context("Too many repeats")?;
+            ensure!(
+                n >= 2,
+                "A number has to repeat at least twice, not {n} times"
+            );
+            Box::new(Repeats { n, radix })
+        }
+        "any-repeats" => Box::new(AnyRepeats { radix }),
+        "palindrome" => Box::new(Palindrome { radix }),
+        "increasing" => Box::new(MonotoneDigits {
+            direction: Direction::Increasing,
+            radix,
+        }),
+        "decreasing" => Box::new(MonotoneDigits {
+            direction: Direction::Decreasing,
+            radix,
+        }),
+        "copies" => Box::new(SubstringCopies {
+            len: number(1)?,
+            copies: number(2)?,
+            radix,
+        }),
+        other => bail!("Unknown pattern {other}"),
+    })
+}
+
+/// Count and sum the numbers in `range` made of a block of digits repeated any number of times.
+///
+/// A number with `digits` digits is a repeat iff it is a `digits / p` digit block repeated `p`
+/// times for some prime `p` dividing `digits`. Being a repeat for both `a` and `b` copies means
+/// being a repeat for `lcm(a, b)` copies, so inclusion–exclusion over the divisors `n > 1` of
+/// `digits` counts each number exactly once, with each term weighted by `-μ(n)`.
+/// Returns `None` if the sum overflows.
+fn totals_with_any_repeats(range: (u128, u128), radix: u32) -> Option<RepeatTotals> {
+    let mut included = RepeatTotals::default();
+    let mut excluded = RepeatTotals::default();
+    for digits in digit_lengths(range, radix) {
+        for n in (2..=digits).filter(|n| digits.is_multiple_of(*n)) {
+            match mobius(n) {
+                -1 => {
+                    included = included.checked_add(totals_of_length(range, digits, n, radix)?)?
+                }
+                1 => excluded = excluded.checked_add(totals_of_length(range, digits, n, radix)?)?,
+                _ => {}
+            }
+        }
+    }
+    Some(RepeatTotals {
+        count: included.count - excluded.count,
+        sum: included.sum - excluded.sum,
+    })
+}
+
+/// Count and sum the numbers in `range` made of a block of digits repeated exactly `n` times.
+///
+/// Every such number with `n * block_len` digits is `block * multiplier`, where `block` is any
+/// number with `block_len` digits, so each digit length is handled in closed form rather than
+/// by enumerating the numbers. Returns `None` if the sum overflows.
+fn totals_with_n_repeats(range: (u128, u128), n: u32, radix: u32) -> Option<RepeatTotals> {
+    digit_lengths(range, radix)
+        .filter(|digits| digits.is_multiple_of(n))
+        .try_fold(RepeatTotals::default(), |totals, digits| {
+            totals.checked_add(totals_of_length(range, digits, n, radix)?)
+        })
 }
 
-fn next_number_with_n_repeats(start: u64, n: u32) -> u64 {
-    let start_str = next_num_with_length_thats_multiple_of_n(start, n).to_string();
-    let (first_part_str, _) = start_str.split_at(start_str.len() / n as usize);
-    let first_part: u64 = first_part_str.parse().unwrap();
-    if repeat_number(first_part, n) >= start {
-        repeat_number(first_part, n)
+/// Count and sum the numbers in `range` with exactly `digits` digits that repeat `n` times
+fn totals_of_length(range: (u128, u128), digits: u32, n: u32, radix: u32) -> Option<RepeatTotals> {
+    let block_len = digits / n;
+    let multiplier = repeat_multiplier(block_len, n, radix)?;
+    let radix = radix as u128;
+    let first = range.0.div_ceil(multiplier).max(radix.pow(block_len - 1));
+    let last = (range.1 / multiplier).min(radix.pow(block_len) - 1);
+    if first > last {
+        return Some(RepeatTotals::default());
+    }
+    let count = last - first + 1;
+    // Sum of first..=last, halving whichever factor is even to avoid needless overflow
+    let block_sum = if count.is_multiple_of(2) {
+        (count / 2).checked_mul(first + last)?
     } else {
-        repeat_number(first_part + 1, n)
+        count.checked_mul((first + last) / 2)?
+    };
+    Some(RepeatTotals {
+        count,
+        sum: block_sum.checked_mul(multiplier)?,
+    })
+}
+
+/// Every digit length found in `range`, or none if the range is empty
+fn digit_lengths(range: (u128, u128), radix: u32) -> std::ops::RangeInclusive<u32> {
+    if range.0 > range.1 {
+        #[allow(clippy::reversed_empty_ranges)]
+        return 1..=0;
+    }
+    num_digits(range.0, radix)..=num_digits(range.1, radix)
+}
+
+fn mobius(n: u32) -> i32 {
+    let mut remaining = n;
+    let mut result = 1;
+    let mut factor = 2;
+    while factor * factor <= remaining {
+        if remaining.is_multiple_of(factor) {
+            remaining /= factor;
+            if remaining.is_multiple_of(factor) {
+                return 0;
+            }
+            result = -result;
+        }
+        factor += 1;
     }
+    if remaining > 1 { -result } else { result }
+}
+
+fn next_number_with_n_repeats(start: u128, n: u32, radix: u32) -> Option<u128> {
+    let start = next_num_with_length_thats_multiple_of_n(start, n, radix)?;
+    let multiplier = repeat_multiplier(num_digits(start, radix) / n, n, radix)?;
+    // Numbers of this length repeating n times are exactly the multiples of `multiplier`
+    repeat_number(start.div_ceil(multiplier), n, radix)
 }
 
-fn repeat_number(num_to_repeat: u64, n: u32) -> u64 {
-    num_to_repeat
-        .to_string()
-        .repeat(n as usize)
-        .parse::<u64>()
-        .unwrap()
+fn repeat_number(num_to_repeat: u128, n: u32, radix: u32) -> Option<u128> {
+    num_to_repeat.checked_mul(repeat_multiplier(
+        num_digits(num_to_repeat, radix),
+        n,
+        radix,
+    )?)
 }
 
-fn next_num_with_length_thats_multiple_of_n(start: u64, n: u32) -> u64 {
-    let digits = start.to_string().len() as u32;
-    if digits % n == 0 {
-        start
+/// The number which repeats a `block_len` digit block `n` times when multiplied by it,
+/// e.g. 10101 for a two digit block repeated three times in base 10.
+fn repeat_multiplier(block_len: u32, n: u32, radix: u32) -> Option<u128> {
+    let shift = (radix as u128).checked_pow(block_len)?;
+    (0..n).try_fold(0_u128, |multiplier, _| {
+        multiplier.checked_mul(shift)?.checked_add(1)
+    })
+}
+
+fn next_num_with_length_thats_multiple_of_n(start: u128, n: u32, radix: u32) -> Option<u128> {
+    let digits = num_digits(start, radix);
+    if digits.is_multiple_of(n) {
+        Some(start)
     } else {
-        10_u64.pow((digits / n + 1) * n - 1)
+        (radix as u128).checked_pow((digits / n + 1) * n - 1)
+    }
+}
+
+/// Digits of `num`, most significant first
+fn digits_of(num: u128, radix: u32) -> Vec<u32> {
+    let mut digits = Vec::new();
+    let mut remaining = num;
+    loop {
+        digits.push((remaining % radix as u128) as u32);
+        remaining /= radix as u128;
+        if remaining == 0 {
+            break;
+        }
     }
+    digits.reverse();
+    digits
+}
+
+fn num_digits(num: u128, radix: u32) -> u32 {
+    num.checked_ilog(radix as u128).map_or(1, |log| log + 1)
 }
 
-fn parse_range(range_string: &str) -> (u64, u64) {
+fn parse_ranges(line: &str, radix: u32) -> Vec<(u128, u128)> {
+    line.split(",")
+        .map(|range_string| parse_range(range_string, radix))
+        .collect()
+}
+
+/// Parse an inclusive `start-end` range written in the given radix (2 to 36)
+fn parse_range(range_string: &str, radix: u32) -> (u128, u128) {
     let split_res: Vec<&str> = range_string.split("-").collect();
     let start = split_res[0];
     let end = split_res[1];
     let msg = "Couldn't parse range string";
-    (start.parse().expect(msg), end.parse().expect(msg))
+    (
+        u128::from_str_radix(start, radix).expect(msg),
+        u128::from_str_radix(end, radix).expect(msg),
+    )
+}
+
+fn merge_ranges(mut ranges: Vec<(u128, u128)>) -> Vec<(u128, u128)> {
+    ranges.sort();
+    let mut merged: Vec<(u128, u128)> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.0 <= last.1.saturating_add(1) => last.1 = last.1.max(range.1),
+            _ => merged.push(range),
+        }
+    }
+    merged
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rstest::rstest;
 
     #[rstest]
     #[case(10, 11)]
@@ -93,8 +433,8 @@ mod tests {
     #[case(1000, 1010)]
     #[case(1012, 1111)]
     #[case(100, 1010)]
-    fn test_next_number_with_2_repeats(#[case] input: u64, #[case] expected: u64) {
-        assert_eq!(next_number_with_n_repeats(input, 2), expected);
+    fn test_next_number_with_2_repeats(#[case] input: u128, #[case] expected: u128) {
+        assert_eq!(next_number_with_n_repeats(input, 2, RADIX), Some(expected));
     }
 
     #[rstest]
@@ -103,8 +443,12 @@ mod tests {
     #[case(100000, 6, 111111)]
     #[case(100000, 5, 1010101010)]
     #[case(2828255673, 5, 2828282828)]
-    fn test_next_number_with_n_repeats(#[case] start: u64, #[case] n: u32, #[case] expected: u64) {
-        assert_eq!(next_number_with_n_repeats(start, n), expected)
+    fn test_next_number_with_n_repeats(
+        #[case] start: u128,
+        #[case] n: u32,
+        #[case] expected: u128,
+    ) {
+        assert_eq!(next_number_with_n_repeats(start, n, RADIX), Some(expected))
     }
 
     #[rstest]
@@ -116,24 +460,236 @@ mod tests {
     #[case(10010, 4, 1000_0000)]
     #[case(1000_0000, 4, 1000_0000)]
     #[case(2828255673, 5, 2828255673)]
-    fn test_correct_number_of_digits(#[case] start: u64, #[case] n: u32, #[case] expected: u64) {
-        assert_eq!(next_num_with_length_thats_multiple_of_n(start, n), expected);
+    fn test_correct_number_of_digits(#[case] start: u128, #[case] n: u32, #[case] expected: u128) {
+        assert_eq!(
+            next_num_with_length_thats_multiple_of_n(start, n, RADIX),
+            Some(expected)
+        );
+    }
+
+    #[rstest]
+    #[case(12, 3, 10, 121212)]
+    #[case(0b10, 2, 2, 0b1010)]
+    #[case(0xab, 3, 16, 0xababab)]
+    #[case(35, 2, 36, 35 * 36 + 35)]
+    fn test_repeat_number(
+        #[case] num: u128,
+        #[case] n: u32,
+        #[case] radix: u32,
+        #[case] expected: u128,
+    ) {
+        assert_eq!(repeat_number(num, n, radix), Some(expected));
+    }
+
+    #[rstest]
+    #[case((11, 22), 2, 10)]
+    #[case((95, 115), 2, 10)]
+    #[case((1, 1_000_000), 3, 10)]
+    #[case((1, 1_000_000), 2, 2)]
+    #[case((500, 70_000), 2, 16)]
+    #[case((0, 50_000), 3, 36)]
+    fn test_totals_match_enumeration(
+        #[case] range: (u128, u128),
+        #[case] n: u32,
+        #[case] radix: u32,
+    ) {
+        let repeats = matches_in(range, &Repeats { n, radix });
+        let expected = RepeatTotals {
+            count: repeats.len() as u128,
+            sum: repeats.iter().sum(),
+        };
+        assert_eq!(totals_with_n_repeats(range, n, radix), Some(expected));
+    }
+
+    #[test]
+    fn test_totals_beyond_u64() {
+        // Every 24 digit number that is a 12 digit block repeated twice
+        let range = (10_u128.pow(23), 10_u128.pow(24) - 1);
+        let (first, last) = (10_u128.pow(11), 10_u128.pow(12) - 1);
+        let expected = RepeatTotals {
+            count: last - first + 1,
+            sum: (10_u128.pow(12) + 1) * (first + last) * (last - first + 1) / 2,
+        };
+        assert_eq!(totals_with_n_repeats(range, 2, RADIX), Some(expected));
     }
 
     #[test]
     fn test_numbers_with_any_repeats() {
-        assert_eq!(numbers_with_any_repeats((11, 22)), [11, 22])
+        assert_eq!(matches_in((11, 22), &AnyRepeats { radix: RADIX }), [11, 22])
+    }
+
+    #[rstest]
+    #[case(1, true)]
+    #[case(10, false)]
+    #[case(12321, true)]
+    #[case(123321, true)]
+    #[case(123421, false)]
+    fn test_palindrome(#[case] id: u128, #[case] expected: bool) {
+        assert_eq!(Palindrome { radix: RADIX }.matches(id), expected);
+    }
+
+    #[rstest]
+    #[case(1123, Direction::Increasing, true)]
+    #[case(1132, Direction::Increasing, false)]
+    #[case(9640, Direction::Decreasing, true)]
+    #[case(9604, Direction::Decreasing, false)]
+    fn test_monotone_digits(
+        #[case] id: u128,
+        #[case] direction: Direction,
+        #[case] expected: bool,
+    ) {
+        let pattern = MonotoneDigits {
+            direction,
+            radix: RADIX,
+        };
+        assert_eq!(pattern.matches(id), expected);
+    }
+
+    #[rstest]
+    #[case(121212, 2, 3, true)]
+    #[case(1212312, 2, 3, true)]
+    #[case(1212, 2, 3, false)]
+    #[case(11122, 1, 3, true)]
+    // Overlapping copies of 11 don't count
+    #[case(111, 2, 2, false)]
+    fn test_substring_copies(
+        #[case] id: u128,
+        #[case] len: usize,
+        #[case] copies: usize,
+        #[case] expected: bool,
+    ) {
+        let pattern = SubstringCopies {
+            len,
+            copies,
+            radix: RADIX,
+        };
+        assert_eq!(pattern.matches(id), expected);
+    }
+
+    #[test]
+    fn test_scanning_for_repeats_matches_jumping_ahead() {
+        let range = (1, 200_000);
+        for n in 2..=6 {
+            let pattern = Repeats { n, radix: RADIX };
+            let scanned: Vec<u128> = (range.0..=range.1)
+                .filter(|&id| pattern.matches(id))
+                .collect();
+            assert_eq!(matches_in(range, &pattern), scanned);
+        }
+        let pattern = AnyRepeats { radix: RADIX };
+        let scanned: Vec<u128> = (range.0..=range.1)
+            .filter(|&id| pattern.matches(id))
+            .collect();
+        assert_eq!(matches_in(range, &pattern), scanned);
+    }
+
+    #[test]
+    fn test_scan_ranges() {
+        let pattern = parse_pattern("palindrome", RADIX).unwrap();
+        let found = scan_ranges("95-115,998-1012", pattern.as_ref());
+        assert_eq!(
+            found,
+            [
+                ((95, 115), vec![99, 101, 111]),
+                ((998, 1012), vec![999, 1001])
+            ]
+        );
+        assert_eq!(solve_part_a("95-115,998-1012", pattern.as_ref()), 2311);
+    }
+
+    #[rstest]
+    #[case("repeats:0")]
+    #[case("repeats:1")]
+    #[case("repeats:4294967298")]
+    #[case("repeats:-2")]
+    fn test_invalid_repeats(#[case] spec: &str) {
+        assert!(parse_pattern(spec, RADIX).is_err());
+    }
+
+    #[rstest]
+    #[case(1, 1)]
+    #[case(2, -1)]
+    #[case(4, 0)]
+    #[case(6, 1)]
+    #[case(12, 0)]
+    #[case(30, -1)]
+    fn test_mobius(#[case] n: u32, #[case] expected: i32) {
+        assert_eq!(mobius(n), expected);
+    }
+
+    #[rstest]
+    #[case((11, 22), 10)]
+    #[case((998, 1012), 10)]
+    #[case((1, 10_000_000), 10)]
+    #[case((1, 1 << 20), 2)]
+    #[case((1, 1_000_000), 16)]
+    #[case((1, 1_000_000), 36)]
+    fn test_any_repeat_totals_match_enumeration(#[case] range: (u128, u128), #[case] radix: u32) {
+        let repeats = matches_in(range, &AnyRepeats { radix });
+        let expected = RepeatTotals {
+            count: repeats.len() as u128,
+            sum: repeats.iter().sum(),
+        };
+        assert_eq!(totals_with_any_repeats(range, radix), Some(expected));
+    }
+
+    #[test]
+    fn test_any_repeat_totals_over_huge_range() {
+        // All 18 digit numbers, far too many to enumerate. Counting the blocks whose smallest
+        // period is each proper divisor of 18 (1, 2, 3, 6 and 9 digits) gives the expected count.
+        let range = (10_u128.pow(17), 10_u128.pow(18) - 1);
+        let count = 9 + 81 + 891 + 899_019 + 899_999_100;
+        let totals = totals_with_any_repeats(range, RADIX).unwrap();
+        assert_eq!(totals.count, count);
+        let (start, end) = range;
+        let (middle, rest) = (start + 123_456_789_012, end - 1);
+        let split = [(start, middle), (middle + 1, rest), (end, end)]
+            .iter()
+            .map(|&range| totals_with_any_repeats(range, RADIX).unwrap())
+            .fold(RepeatTotals::default(), |acc, t| {
+                acc.checked_add(t).unwrap()
+            });
+        assert_eq!(split, totals);
+    }
+
+    #[test]
+    fn test_merge_ranges() {
+        assert_eq!(
+            merge_ranges(vec![(20, 30), (1, 5), (6, 8), (25, 40)]),
+            [(1, 8), (20, 40)]
+        );
     }
 
     #[test]
     fn test_part_a() {
-        assert_eq!(1227775554, solve_part_a(&load_input(2, Input::Test)[0]));
-        assert_eq!(38158151648, solve_part_a(&load_input(2, Input::Puzzle)[0]));
+        assert_eq!(
+            1227775554,
+            solve_part_a(
+                &load_input(2, Input::Test)[0],
+                &Repeats { n: 2, radix: RADIX }
+            )
+        );
+        assert_eq!(
+            38158151648,
+            solve_part_a(
+                &load_input(2, Input::Puzzle)[0],
+                &Repeats { n: 2, radix: RADIX }
+            )
+        );
     }
 
     #[test]
     fn test_part_b() {
-        assert_eq!(4174379265, solve_part_b(&load_input(2, Input::Test)[0]));
-        assert_eq!(45283684555, solve_part_b(&load_input(2, Input::Puzzle)[0]));
+        assert_eq!(
+            4174379265,
+            solve_part_b(&load_input(2, Input::Test)[0], &AnyRepeats { radix: RADIX })
+        );
+        assert_eq!(
+            45283684555,
+            solve_part_b(
+                &load_input(2, Input::Puzzle)[0],
+                &AnyRepeats { radix: RADIX }
+            )
+        );
     }
 }
@@ -1,28 +1,124 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, ensure};
 use aoc25::{Input, load_input};
-use itertools::Itertools;
+
+/// Digits picked out of a line, with the index each one was taken from
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Subsequence {
+    digits: Vec<u32>,
+    indices: Vec<usize>,
+}
+
+impl Subsequence {
+    fn to_digit_string(&self) -> String {
+        self.digits
+            .iter()
+            .map(|&d| char::from_digit(d, 10).expect("Not a decimal digit"))
+            .collect()
+    }
+
+    fn to_u64(&self) -> Result<u64> {
+        self.digits.iter().try_fold(0_u64, |num, &digit| {
+            num.checked_mul(10)
+                .and_then(|num| num.checked_add(digit as u64))
+                .context("Number doesn't fit in a u64")
+        })
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+enum Order {
+    #[default]
+    Largest,
+    Smallest,
+}
+
+/// How digits are picked. The default is the puzzle's largest subsequence.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+struct PickOptions {
+    order: Order,
+    no_leading_zero: bool,
+    /// No two picked digits may sit next to each other in the line
+    no_adjacent: bool,
+    /// Index of a digit that must be picked
+    must_include: Option<usize>,
+}
+
+impl PickOptions {
+    fn is_constrained(&self) -> bool {
+        self.no_leading_zero || self.no_adjacent || self.must_include.is_some()
+    }
+
+    /// Whether a digit should be picked over the current best
+    fn beats(&self, digit: u32, best: u32) -> bool {
+        match self.order {
+            Order::Largest => digit > best,
+            Order::Smallest => digit < best,
+        }
+    }
+}
 
 fn main() {
     let lines = load_input(3, Input::Puzzle);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(n) = args.iter().skip_while(|arg| *arg != "--digits").nth(1) {
+        let n = n.parse().expect("Couldn't parse number of digits");
+        let options = parse_options(&args).expect("Couldn't parse options");
+        let sum = sum_picked_strings(&lines, n, &options).expect("Failed to solve");
+        println!("Sum of {n} digit numbers picked with {options:?}: {sum}");
+        return;
+    }
     let a = solve_part_a(&lines);
     println!("Solution to a: {a}");
     let b = solve_part_b(&lines);
     println!("Solution to b: {b}");
 }
 
-fn sum_largest_n_digit_numbers(lines: &[String], n: u32) -> Result<u64> {
+fn parse_options(args: &[String]) -> Result<PickOptions> {
+    let has_flag = |flag: &str| args.iter().any(|arg| arg == flag);
+    let must_include = match args.iter().skip_while(|arg| *arg != "--include").nth(1) {
+        Some(idx) => Some(idx.parse().context("Couldn't parse index to include")?),
+        None => None,
+    };
+    Ok(PickOptions {
+        order: if has_flag("--smallest") {
+            Order::Smallest
+        } else {
+            Order::Largest
+        },
+        no_leading_zero: has_flag("--no-leading-zero"),
+        no_adjacent: has_flag("--no-adjacent"),
+        must_include,
+    })
+}
+
+/// Sum the picked `n` digit number from each line. Picks the largest unless `options` say otherwise.
+fn sum_picked_numbers(lines: &[String], n: u32, options: &PickOptions) -> Result<u64> {
     lines
         .iter()
-        .map(|line| largest_n_digit_num(&parse_line(&line)?, n))
+        .map(|line| largest_n_digit_num(&parse_line(line)?, n, options))
         .sum()
 }
 
+/// As `sum_picked_numbers`, but exact for any number of digits
+fn sum_picked_strings(lines: &[String], n: usize, options: &PickOptions) -> Result<String> {
+    let mut sum = vec![0];
+    for line in lines {
+        let picked = pick_subsequence(&parse_line(line)?, n, options)?;
+        sum = add_digits(&sum, &picked.digits);
+    }
+    Ok(Subsequence {
+        digits: sum,
+        indices: Vec::new(),
+    }
+    .to_digit_string())
+}
+
 fn solve_part_a(lines: &[String]) -> u64 {
-    sum_largest_n_digit_numbers(lines, 2).expect("Failed to solve")
+    sum_picked_numbers(lines, 2, &PickOptions::default()).expect("Failed to solve")
 }
 
 fn solve_part_b(lines: &[String]) -> u64 {
-    sum_largest_n_digit_numbers(lines, 12).expect("Failed to solve")
+    sum_picked_numbers(lines, 12, &PickOptions::default()).expect("Failed to solve")
 }
 
 fn parse_line(line: &str) -> Result<Vec<u32>> {
@@ -31,31 +127,114 @@ fn parse_line(line: &str) -> Result<Vec<u32>> {
         .collect()
 }
 
-fn largest_n_digit_num(input: &[u32], n: u32) -> Result<u64> {
-    if n < 2 {
-        input
-            .iter()
-            .max()
-            .context("Input is empty")
-            .map(|&val| val as u64)
+fn largest_n_digit_num(input: &[u32], n: u32, options: &PickOptions) -> Result<u64> {
+    pick_subsequence(input, n as usize, options)?.to_u64()
+}
+
+fn pick_subsequence(input: &[u32], n: usize, options: &PickOptions) -> Result<Subsequence> {
+    ensure!(n > 0, "Must pick at least one digit");
+    ensure!(
+        n <= input.len(),
+        "Can't pick {n} digits from {}",
+        input.len()
+    );
+    let indices = if options.is_constrained() {
+        constrained_pick(input, n, options)?
     } else {
-        // We can't pick any of the last n-1 chars as the number must be n digits
-        let search_space_end = input.len() + 1 - n as usize;
+        stack_pick(input, n, options)
+    };
+    Ok(Subsequence {
+        digits: indices.iter().map(|&idx| input[idx]).collect(),
+        indices,
+    })
+}
 
-        let reversed_idx = &input[..search_space_end]
-            .iter()
-            .rev() // `position_max` gives position of last item if several are equally maximum
-            .position_max()
-            .context("Couldn't find max position")?;
+/// Indices of the `n` digits of `input`, kept in order, that make the largest (or smallest) number.
+///
+/// Works left to right keeping a stack of picks that only ever decreases: a new digit knocks
+/// smaller digits off the top while there are still enough digits left to fill `n` places.
+/// Each digit is pushed and popped at most once, so this is O(len) for any `n`.
+/// Equal digits are never knocked off, so ties go to the leftmost digit.
+fn stack_pick(input: &[u32], n: usize, options: &PickOptions) -> Vec<usize> {
+    let mut removals_left = input.len() - n;
+    let mut stack: Vec<usize> = Vec::with_capacity(input.len());
+    for (idx, &digit) in input.iter().enumerate() {
+        while removals_left > 0
+            && stack
+                .last()
+                .is_some_and(|&top| options.beats(digit, input[top]))
+        {
+            stack.pop();
+            removals_left -= 1;
+        }
+        stack.push(idx);
+    }
+    stack.truncate(n);
+    stack
+}
 
-        // Convert reversed position to original index
-        let max_digit_idx = search_space_end - 1 - reversed_idx;
+/// Picks one digit at a time, taking the best digit from which the rest can still be picked
+/// within the constraints. Ties go to the leftmost digit as that leaves the most choice for
+/// later picks. O(len * n).
+fn constrained_pick(input: &[u32], n: usize, options: &PickOptions) -> Result<Vec<usize>> {
+    let gap = if options.no_adjacent { 2 } else { 1 };
+    let mut picks: Vec<usize> = Vec::with_capacity(n);
+    let mut start = 0;
+    for still_needed in (0..n).rev() {
+        let best = (start..input.len())
+            .filter(|&idx| !(picks.is_empty() && options.no_leading_zero && input[idx] == 0))
+            .filter(|&idx| {
+                options
+                    .must_include
+                    .is_none_or(|inc| inc < start || idx <= inc)
+            })
+            .filter(|&idx| {
+                let include = options.must_include.filter(|&inc| inc > idx);
+                can_pick(idx + gap, input.len(), still_needed, include, gap)
+            })
+            .fold(None, |best: Option<usize>, idx| match best {
+                Some(b) if !options.beats(input[idx], input[b]) => Some(b),
+                _ => Some(idx),
+            })
+            .context("No subsequence satisfies the constraints")?;
+        picks.push(best);
+        start = best + gap;
+    }
+    Ok(picks)
+}
 
-        let next_input = &input[max_digit_idx + 1..];
+/// Whether `picks` digits at least `gap` apart can be taken from `start..len`, including
+/// the index `include` if given
+fn can_pick(start: usize, len: usize, picks: usize, include: Option<usize>, gap: usize) -> bool {
+    let capacity = |from: usize, to: usize| to.saturating_sub(from).div_ceil(gap);
+    match include {
+        None => picks <= capacity(start, len),
+        Some(inc) => {
+            inc >= start
+                && picks >= 1
+                && picks - 1
+                    <= capacity(start, (inc + 1).saturating_sub(gap)) + capacity(inc + gap, len)
+        }
+    }
+}
 
-        Ok(input[max_digit_idx] as u64 * 10_u64.pow(n - 1)
-            + largest_n_digit_num(&next_input, n - 1)?)
+/// Add two numbers given as decimal digits, most significant first
+fn add_digits(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    let mut a_iter = a.iter().rev();
+    let mut b_iter = b.iter().rev();
+    loop {
+        let (x, y) = (a_iter.next(), b_iter.next());
+        if x.is_none() && y.is_none() && carry == 0 {
+            break;
+        }
+        let total = x.unwrap_or(&0) + y.unwrap_or(&0) + carry;
+        result.push(total % 10);
+        carry = total / 10;
     }
+    result.reverse();
+    result
 }
 
 #[cfg(test)]
@@ -76,11 +255,97 @@ mod tests {
     #[case("818181911112111", 888911112111)]
     fn test_largest_n_digit_num(#[case] input: String, #[case] expected: u64) {
         assert_eq!(
-            largest_n_digit_num(&parse_line(&input).unwrap(), 12).unwrap(),
+            largest_n_digit_num(&parse_line(&input).unwrap(), 12, &PickOptions::default()).unwrap(),
             expected
         )
     }
 
+    #[test]
+    fn test_largest_subsequence_indices() {
+        let largest = pick_subsequence(
+            &parse_line("818181911112111").unwrap(),
+            4,
+            &PickOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(largest.digits, [9, 2, 1, 1]);
+        assert_eq!(largest.indices, [6, 11, 12, 13]);
+        let ties =
+            pick_subsequence(&parse_line("9919").unwrap(), 2, &PickOptions::default()).unwrap();
+        assert_eq!(ties.indices, [0, 1]);
+    }
+
+    #[test]
+    fn test_largest_subsequence_hundreds_of_digits() {
+        let line = "9".repeat(150) + &"1".repeat(150) + &"8".repeat(150);
+        let largest =
+            pick_subsequence(&parse_line(&line).unwrap(), 300, &PickOptions::default()).unwrap();
+        assert_eq!(
+            largest.to_digit_string(),
+            "9".repeat(150) + &"8".repeat(150)
+        );
+        assert!(largest.to_u64().is_err());
+    }
+
+    #[test]
+    fn test_sum_picked_strings() {
+        let lines = ["987654321111111".to_string(), "811111111111119".to_string()];
+        assert_eq!(
+            sum_picked_strings(&lines, 12, &PickOptions::default()).unwrap(),
+            (987654321111_u64 + 811111111119).to_string()
+        );
+    }
+
+    #[rstest]
+    #[case("4321", 2, PickOptions { order: Order::Smallest, ..Default::default() }, "21")]
+    #[case("10200", 2, PickOptions { order: Order::Smallest, ..Default::default() }, "00")]
+    #[case("10200", 2, PickOptions { order: Order::Smallest, no_leading_zero: true, ..Default::default() }, "10")]
+    #[case("9919", 2, PickOptions { no_adjacent: true, ..Default::default() }, "99")]
+    #[case("98989", 3, PickOptions { no_adjacent: true, ..Default::default() }, "999")]
+    #[case("98989", 3, PickOptions { no_adjacent: true, order: Order::Smallest, ..Default::default() }, "999")]
+    #[case("987654321", 3, PickOptions { must_include: Some(8), ..Default::default() }, "981")]
+    #[case("123456789", 3, PickOptions { must_include: Some(0), order: Order::Smallest, ..Default::default() }, "123")]
+    #[case("818181911112111", 12, PickOptions { must_include: Some(0), ..Default::default() }, "888911112111")]
+    fn test_pick_subsequence(
+        #[case] input: &str,
+        #[case] n: usize,
+        #[case] options: PickOptions,
+        #[case] expected: &str,
+    ) {
+        let picked = pick_subsequence(&parse_line(input).unwrap(), n, &options).unwrap();
+        assert_eq!(picked.to_digit_string(), expected);
+    }
+
+    #[rstest]
+    #[case("0091", 3, PickOptions { no_leading_zero: true, ..Default::default() })]
+    #[case("12345", 4, PickOptions { no_adjacent: true, ..Default::default() })]
+    #[case("12345", 3, PickOptions { no_adjacent: true, must_include: Some(1), ..Default::default() })]
+    fn test_pick_subsequence_impossible(
+        #[case] input: &str,
+        #[case] n: usize,
+        #[case] options: PickOptions,
+    ) {
+        assert!(pick_subsequence(&parse_line(input).unwrap(), n, &options).is_err());
+    }
+
+    #[test]
+    fn test_constrained_pick_matches_stack() {
+        // With no constraints the one-at-a-time picker must agree with the stack
+        let input = parse_line("818181911112111234234234234278").unwrap();
+        for order in [Order::Largest, Order::Smallest] {
+            let options = PickOptions {
+                order,
+                ..Default::default()
+            };
+            for n in 1..=input.len() {
+                assert_eq!(
+                    constrained_pick(&input, n, &options).unwrap(),
+                    stack_pick(&input, n, &options)
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_part_b() {
         assert_eq!(3121910778619, solve_part_b(&load_input(3, Input::Test)));
@@ -1,9 +1,16 @@
+use anyhow::{Context, Result, bail};
 use aoc25::{Input, load_input};
-use std::collections::HashMap;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use std::cell::LazyCell;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::hash::Hash;
+use std::path::Path;
 
 const DAY: u8 = 4;
+const CELL_PIXELS: u32 = 4;
 
-#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
 enum State {
     Filled,
     Empty,
@@ -16,68 +23,648 @@ struct Coord {
     y: i32,
 }
 
+/// Which cells count as neighbours, relative to a cell
+#[derive(PartialEq, Eq, Debug, Clone)]
+enum Neighbourhood {
+    /// The 4 orthogonally adjacent cells
+    VonNeumann,
+    /// All 8 surrounding cells
+    Moore,
+    /// The 6 cells around a hexagon, with odd rows shifted half a cell to the right
+    Hexagonal,
+    Custom(Vec<Coord>),
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+enum Edges {
+    /// Cells off the edge of the grid don't exist
+    Bounded,
+    /// The grid wraps around, so the left edge neighbours the right and the top the bottom
+    Toroidal,
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+enum UpdateMode {
+    /// Every removable cell goes at once, then neighbour counts are updated
+    Synchronous,
+    /// One cell goes at a time, taking the first removable cell in reading order
+    OneAtATime,
+}
+
+/// Which cells neighbour each other. Defaults to the puzzle's bounded Moore neighbourhood.
+#[derive(PartialEq, Eq, Debug, Clone)]
+struct Topology {
+    neighbourhood: Neighbourhood,
+    edges: Edges,
+}
+
+/// A filled cell is removed when it has fewer than `threshold` filled neighbours
+#[derive(PartialEq, Eq, Debug, Clone)]
+struct ErosionRules {
+    topology: Topology,
+    threshold: usize,
+    update: UpdateMode,
+}
+
+impl Default for Topology {
+    fn default() -> Self {
+        Topology {
+            neighbourhood: Neighbourhood::Moore,
+            edges: Edges::Bounded,
+        }
+    }
+}
+
+impl Default for ErosionRules {
+    /// The puzzle's rules
+    fn default() -> Self {
+        ErosionRules {
+            topology: Topology::default(),
+            threshold: 4,
+            update: UpdateMode::Synchronous,
+        }
+    }
+}
+
+impl Topology {
+    fn offsets(&self, loc: &Coord) -> Vec<Coord> {
+        let offset = |(x, y): &(i32, i32)| Coord { x: *x, y: *y };
+        match &self.neighbourhood {
+            Neighbourhood::VonNeumann => [(-1, 0), (0, -1), (0, 1), (1, 0)]
+                .iter()
+                .map(offset)
+                .collect(),
+            Neighbourhood::Moore => [-1, 0, 1]
+                .into_iter()
+                .flat_map(|x| [-1, 0, 1].into_iter().map(move |y| Coord { x, y }))
+                .filter(|c| *c != Coord { x: 0, y: 0 })
+                .collect(),
+            Neighbourhood::Hexagonal if loc.y.rem_euclid(2) == 0 => {
+                [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, 0)]
+                    .iter()
+                    .map(offset)
+                    .collect()
+            }
+            Neighbourhood::Hexagonal => [(-1, 0), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)]
+                .iter()
+                .map(offset)
+                .collect(),
+            Neighbourhood::Custom(offsets) => offsets.clone(),
+        }
+    }
+}
+
+/// A cell removed during erosion, and how many filled neighbours it had when it went
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+struct Removal {
+    coord: Coord,
+    filled_neighbours: usize,
+}
+
+/// The starting grid and the cells removed in each round of erosion
+#[derive(Debug)]
+struct ErosionHistory {
+    initial: Vec<Vec<State>>,
+    rounds: Vec<Vec<Removal>>,
+}
+
+impl ErosionHistory {
+    fn removed_count(&self) -> usize {
+        self.rounds.iter().map(|round| round.len()).sum()
+    }
+
+    /// The grid after the first `num_rounds` rounds, with removed cells marked as taken
+    fn grid_after(&self, num_rounds: usize) -> Vec<Vec<State>> {
+        let mut grid = self.initial.clone();
+        for removal in self.rounds[..num_rounds].iter().flatten() {
+            set_state_at(removal.coord, State::Taken, &mut grid);
+        }
+        grid
+    }
+
+    /// One frame for the starting grid, then one after each round
+    fn ascii_frames(&self) -> Vec<String> {
+        (0..=self.rounds.len())
+            .map(|num_rounds| {
+                self.grid_after(num_rounds)
+                    .iter()
+                    .map(|line| line.iter().map(state_to_char).collect::<String>())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .collect()
+    }
+
+    /// The round each cell was removed in, if it was
+    fn removal_rounds(&self) -> HashMap<Coord, usize> {
+        self.rounds
+            .iter()
+            .enumerate()
+            .flat_map(|(round, removals)| removals.iter().map(move |r| (r.coord, round)))
+            .collect()
+    }
+
+    fn write_gif(&self, path: &Path, frame_delay_ms: u32) -> Result<()> {
+        let root = BitMapBackend::gif(path, self.image_size(), frame_delay_ms)?.into_drawing_area();
+        let removal_rounds = self.removal_rounds();
+        for num_rounds in 0..=self.rounds.len() {
+            self.draw_frame(&root, &removal_rounds, num_rounds)?;
+            root.present()?;
+        }
+        Ok(())
+    }
+
+    fn write_png_series(&self, dir: &Path) -> Result<()> {
+        let removal_rounds = self.removal_rounds();
+        for num_rounds in 0..=self.rounds.len() {
+            let path = dir.join(format!("day4_round_{num_rounds:03}.png"));
+            let root = BitMapBackend::new(&path, self.image_size()).into_drawing_area();
+            self.draw_frame(&root, &removal_rounds, num_rounds)?;
+            root.present()?;
+        }
+        Ok(())
+    }
+
+    fn image_size(&self) -> (u32, u32) {
+        let size = size(&self.initial);
+        (size.x as u32 * CELL_PIXELS, size.y as u32 * CELL_PIXELS)
+    }
+
+    /// Filled cells are black. Removed cells are shaded by the round they went in, so the
+    /// peel order shows as a gradient, and the latest round is red. `removal_rounds` is
+    /// `self.removal_rounds()`, worked out once for all the frames.
+    fn draw_frame<DB: DrawingBackend>(
+        &self,
+        area: &DrawingArea<DB, Shift>,
+        removal_rounds: &HashMap<Coord, usize>,
+        num_rounds: usize,
+    ) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+        area.fill(&WHITE)?;
+        for (y, line) in self.initial.iter().enumerate() {
+            for (x, state) in line.iter().enumerate() {
+                let coord = Coord {
+                    x: x as i32,
+                    y: y as i32,
+                };
+                let colour = match (state, removal_rounds.get(&coord)) {
+                    (State::Empty, _) => continue,
+                    (_, Some(&round)) if round + 1 == num_rounds => RED.to_rgba(),
+                    (_, Some(&round)) if round < num_rounds => {
+                        let progress = round as f64 / self.rounds.len().max(1) as f64;
+                        HSLColor(0.55 + 0.2 * progress, 0.7, 0.75).to_rgba()
+                    }
+                    _ => BLACK.to_rgba(),
+                };
+                let top_left = (x as i32 * CELL_PIXELS as i32, y as i32 * CELL_PIXELS as i32);
+                let bottom_right = (
+                    top_left.0 + CELL_PIXELS as i32 - 1,
+                    top_left.1 + CELL_PIXELS as i32 - 1,
+                );
+                area.draw(&Rectangle::new([top_left, bottom_right], colour.filled()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One bit per cell, set for filled cells, packed row by row into words
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct BitGrid {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    fn new(width: usize, height: usize) -> BitGrid {
+        let words_per_row = width.div_ceil(64);
+        BitGrid {
+            width,
+            height,
+            words_per_row,
+            words: vec![0; words_per_row * height],
+        }
+    }
+
+    fn parse(lines: &[String]) -> BitGrid {
+        let width = lines.first().map_or(0, |line| line.len());
+        let mut grid = BitGrid::new(width, lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                if parse_location(c) == State::Filled {
+                    grid.set(x, y);
+                }
+            }
+        }
+        grid
+    }
+
+    fn word_and_mask(&self, x: usize, y: usize) -> (usize, u64) {
+        (y * self.words_per_row + x / 64, 1 << (x % 64))
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        let (word, mask) = self.word_and_mask(x, y);
+        self.words[word] & mask != 0
+    }
+
+    fn set(&mut self, x: usize, y: usize) {
+        let (word, mask) = self.word_and_mask(x, y);
+        self.words[word] |= mask;
+    }
+
+    fn clear(&mut self, x: usize, y: usize) {
+        let (word, mask) = self.word_and_mask(x, y);
+        self.words[word] &= !mask;
+    }
+
+    fn count_filled(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// The Moore neighbours of a cell that are on the grid
+    fn neighbours(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + use<> {
+        let (width, height) = (self.width, self.height);
+        (y.saturating_sub(1)..=(y + 1).min(height - 1))
+            .flat_map(move |ny| {
+                (x.saturating_sub(1)..=(x + 1).min(width - 1)).map(move |nx| (nx, ny))
+            })
+            .filter(move |&neighbour| neighbour != (x, y))
+    }
+
+    fn filled_neighbours(&self, x: usize, y: usize) -> usize {
+        self.neighbours(x, y)
+            .filter(|&(nx, ny)| self.get(nx, ny))
+            .count()
+    }
+}
+
+/// A cell state for the cellular automaton. Only whether a neighbour is alive is counted.
+trait CellState: Copy + Eq + Hash {
+    fn is_alive(&self) -> bool;
+}
+
+impl CellState for State {
+    fn is_alive(&self) -> bool {
+        *self == State::Filled
+    }
+}
+
+/// The state a cell moves to, given its current state and how many live neighbours it has
+trait CellRule<S: CellState> {
+    fn next_state(&self, state: S, live_neighbours: usize) -> S;
+}
+
+/// A life-like rule, such as Conway's Life which is "B3/S23": dead cells with a neighbour count
+/// in `birth` come alive, and live cells with a count in `survival` stay alive
+#[derive(PartialEq, Eq, Debug, Clone)]
+struct LifeRule {
+    birth: Vec<usize>,
+    survival: Vec<usize>,
+}
+
+impl CellRule<State> for LifeRule {
+    fn next_state(&self, state: State, live_neighbours: usize) -> State {
+        match state {
+            State::Filled if self.survival.contains(&live_neighbours) => State::Filled,
+            State::Filled => State::Empty,
+            _ if self.birth.contains(&live_neighbours) => State::Filled,
+            other => other,
+        }
+    }
+}
+
+/// Day 4's erosion as a synchronous automaton: filled cells with too few filled neighbours
+/// are taken, and nothing ever comes back
+struct ErosionRule {
+    threshold: usize,
+}
+
+impl CellRule<State> for ErosionRule {
+    fn next_state(&self, state: State, live_neighbours: usize) -> State {
+        match state {
+            State::Filled if live_neighbours < self.threshold => State::Taken,
+            other => other,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+enum RunOutcome {
+    /// Stepping no longer changes the grid, as of this generation
+    Stable { generation: usize },
+    /// The grid at `generation` repeats every `period` generations
+    Cycle { generation: usize, period: usize },
+    /// Still changing when the generation limit was reached
+    Unsettled,
+}
+
+/// Steps a grid of cells forward, all cells updating at once
+struct Automaton<S, R> {
+    grid: Vec<Vec<S>>,
+    rule: R,
+    topology: Topology,
+    generation: usize,
+}
+
+impl<S: CellState, R: CellRule<S>> Automaton<S, R> {
+    fn new(grid: Vec<Vec<S>>, rule: R, topology: Topology) -> Self {
+        Automaton {
+            grid,
+            rule,
+            topology,
+            generation: 0,
+        }
+    }
+
+    fn step(&mut self) {
+        let size = size(&self.grid);
+        let next = (0..size.y)
+            .map(|y| {
+                (0..size.x)
+                    .map(|x| {
+                        let coord = Coord { x, y };
+                        let live_neighbours = neighbours(&coord, &size, &self.topology)
+                            .iter()
+                            .filter(|n| state_at(n, &self.grid).is_alive())
+                            .count();
+                        self.rule
+                            .next_state(state_at(&coord, &self.grid), live_neighbours)
+                    })
+                    .collect()
+            })
+            .collect();
+        self.grid = next;
+        self.generation += 1;
+    }
+
+    /// Step until the grid stops changing or repeats an earlier grid, or for at most
+    /// `max_generations` if given
+    fn run(&mut self, max_generations: Option<usize>) -> RunOutcome {
+        let mut seen: HashMap<Vec<Vec<S>>, usize> = HashMap::new();
+        seen.insert(self.grid.clone(), self.generation);
+        let start = self.generation;
+        while max_generations.is_none_or(|max| self.generation - start < max) {
+            self.step();
+            if let Some(&generation) = seen.get(&self.grid) {
+                let period = self.generation - generation;
+                return if period == 1 {
+                    RunOutcome::Stable { generation }
+                } else {
+                    RunOutcome::Cycle { generation, period }
+                };
+            }
+            seen.insert(self.grid.clone(), self.generation);
+        }
+        RunOutcome::Unsettled
+    }
+
+    fn live_count(&self) -> usize {
+        self.grid.iter().flatten().filter(|s| s.is_alive()).count()
+    }
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let arg_value = |flag: &str| args.iter().skip_while(|arg| *arg != flag).nth(1);
     let lines = load_input(DAY, Input::Puzzle);
     let a = solve_part_a(&lines);
     println!("Solution to a: {a}");
     let b = solve_part_b(&lines);
     println!("Solution to b: {b}");
+
+    let rules = parse_rules(&args).expect("Couldn't parse erosion rules");
+    // Only worked out if one of the options below needs it
+    let history = LazyCell::new(|| erode(&parse_grid(&lines), &rules));
+    if rules != ErosionRules::default() {
+        println!("Removed {} cells with {rules:?}", history.removed_count());
+    }
+    if args.iter().any(|arg| arg == "--ascii") {
+        for (num_rounds, frame) in history.ascii_frames().iter().enumerate() {
+            println!("After {num_rounds} rounds:\n{frame}\n");
+        }
+    }
+    if let Some(rule) = arg_value("--automaton") {
+        let max_generations = arg_value("--generations")
+            .map(|n| n.parse().expect("Couldn't parse number of generations"));
+        let grid = parse_grid(&lines);
+        let topology = rules.topology.clone();
+        if rule == "erosion" {
+            let rule = ErosionRule {
+                threshold: rules.threshold,
+            };
+            run_automaton(Automaton::new(grid, rule, topology), max_generations);
+        } else {
+            let rule = parse_life_rule(rule).expect("Couldn't parse life rule");
+            run_automaton(Automaton::new(grid, rule, topology), max_generations);
+        }
+    }
+    if let Some(path) = arg_value("--gif") {
+        history
+            .write_gif(Path::new(path), 200)
+            .expect("Couldn't write gif");
+    }
+    if let Some(dir) = arg_value("--png-dir") {
+        history
+            .write_png_series(Path::new(dir))
+            .expect("Couldn't write pngs");
+    }
+}
+
+/// Parse `--neighbourhood moore|von-neumann|hex|custom:DX,DY;DX,DY...`, `--threshold N`,
+/// `--torus` and `--one-at-a-time`, defaulting to the puzzle's rules
+fn parse_rules(args: &[String]) -> Result<ErosionRules> {
+    let arg_value = |flag: &str| args.iter().skip_while(|arg| *arg != flag).nth(1);
+    let has_flag = |flag: &str| args.iter().any(|arg| arg == flag);
+    let mut rules = ErosionRules::default();
+    if let Some(neighbourhood) = arg_value("--neighbourhood") {
+        rules.topology.neighbourhood = match neighbourhood.as_str() {
+            "moore" => Neighbourhood::Moore,
+            "von-neumann" => Neighbourhood::VonNeumann,
+            "hex" => Neighbourhood::Hexagonal,
+            custom if custom.starts_with("custom:") => Neighbourhood::Custom(
+                custom["custom:".len()..]
+                    .split(';')
+                    .map(parse_offset)
+                    .collect::<Result<_>>()?,
+            ),
+            other => bail!("Unknown neighbourhood {other}"),
+        };
+    }
+    if let Some(threshold) = arg_value("--threshold") {
+        rules.threshold = threshold.parse().context("Couldn't parse threshold")?;
+    }
+    if has_flag("--torus") {
+        rules.topology.edges = Edges::Toroidal;
+    }
+    if has_flag("--one-at-a-time") {
+        rules.update = UpdateMode::OneAtATime;
+    }
+    Ok(rules)
+}
+
+fn run_automaton<S: CellState, R: CellRule<S>>(
+    mut automaton: Automaton<S, R>,
+    max_generations: Option<usize>,
+) {
+    let outcome = automaton.run(max_generations);
+    println!(
+        "{outcome:?} after {} generations, with {} live cells",
+        automaton.generation,
+        automaton.live_count()
+    );
+}
+
+/// Parse a rule in "B3/S23" notation
+fn parse_life_rule(rule: &str) -> Result<LifeRule> {
+    let mut birth = None;
+    let mut survival = None;
+    for part in rule.split('/') {
+        let counts = || -> Result<Vec<usize>> {
+            part[1..]
+                .chars()
+                .map(|c| {
+                    c.to_digit(10)
+                        .map(|d| d as usize)
+                        .context("Expected a digit")
+                })
+                .collect()
+        };
+        match part.chars().next().map(|c| c.to_ascii_uppercase()) {
+            Some('B') => birth = Some(counts()?),
+            Some('S') => survival = Some(counts()?),
+            _ => bail!("Expected B or S at the start of {part}"),
+        }
+    }
+    Ok(LifeRule {
+        birth: birth.context("Missing birth counts")?,
+        survival: survival.context("Missing survival counts")?,
+    })
+}
+
+fn parse_offset(offset: &str) -> Result<Coord> {
+    let (x, y) = offset.split_once(',').context("Offset should be DX,DY")?;
+    Ok(Coord {
+        x: x.parse().context("Couldn't parse offset")?,
+        y: y.parse().context("Couldn't parse offset")?,
+    })
 }
 
 fn solve_part_a(lines: &[String]) -> usize {
-    let grid: Vec<Vec<State>> = lines.iter().map(|l| parse_line(&l)).collect();
-    filled_location_with_num_filled_neighbours(&grid)
+    let grid = parse_grid(lines);
+    let rules = ErosionRules::default();
+    filled_location_with_num_filled_neighbours(&grid, &rules)
         .iter()
-        .filter(|c| *c.1 < 4)
+        .filter(|c| *c.1 < rules.threshold)
         .count()
 }
 
 fn solve_part_b(lines: &[String]) -> usize {
-    let print = false;
-    let mut grid: Vec<Vec<State>> = lines.iter().map(|l| parse_line(&l)).collect();
-    let size = size(&grid);
-    let mut filled_locations = filled_location_with_num_filled_neighbours(&grid);
-    let initial_filled = filled_locations.len();
+    peel(
+        &mut BitGrid::parse(lines),
+        ErosionRules::default().threshold,
+    )
+}
+
+/// Remove filled cells with fewer than `threshold` filled Moore neighbours until none are left,
+/// returning how many went. Gives the same count as `erode`, because the cells that end up
+/// removed don't depend on the order they go in.
+///
+/// Only the neighbours of a removed cell can become removable, so rather than rescanning the
+/// grid each round, those neighbours are checked and queued as cells are removed.
+fn peel(grid: &mut BitGrid, threshold: usize) -> usize {
+    let mut queued = BitGrid::new(grid.width, grid.height);
+    let mut queue = VecDeque::new();
+    for y in 0..grid.height {
+        for x in 0..grid.width {
+            if grid.get(x, y) && grid.filled_neighbours(x, y) < threshold {
+                queued.set(x, y);
+                queue.push_back((x, y));
+            }
+        }
+    }
+    let initial_filled = grid.count_filled();
+    while let Some((x, y)) = queue.pop_front() {
+        grid.clear(x, y);
+        for (nx, ny) in grid.neighbours(x, y) {
+            if grid.get(nx, ny) && !queued.get(nx, ny) && grid.filled_neighbours(nx, ny) < threshold
+            {
+                queued.set(nx, ny);
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    initial_filled - grid.count_filled()
+}
+
+/// Repeatedly remove filled cells with too few filled neighbours until none are left
+fn erode(grid: &[Vec<State>], rules: &ErosionRules) -> ErosionHistory {
+    let size = size(grid);
+    let mut filled_locations = filled_location_with_num_filled_neighbours(grid, rules);
+    // Cells that can be removed, as (y, x) so they're kept in reading order
+    let mut removable: BTreeSet<(i32, i32)> = filled_locations
+        .iter()
+        .filter(|(_, filled_neighbours)| **filled_neighbours < rules.threshold)
+        .map(|(coord, _)| (coord.y, coord.x))
+        .collect();
+    let mut rounds = Vec::new();
     loop {
-        let removables: Vec<Coord> = filled_locations
+        let batch_size = match rules.update {
+            UpdateMode::Synchronous => removable.len(),
+            UpdateMode::OneAtATime => 1,
+        };
+        let batch: Vec<Coord> = removable
             .iter()
-            .filter(|(_, filled_neighbours)| **filled_neighbours < 4)
-            .map(|(coord, _)| *coord)
+            .take(batch_size)
+            .map(|&(y, x)| Coord { x, y })
             .collect();
-        if removables.len() == 0 {
+        if batch.is_empty() {
             break;
         }
-        for removable in removables {
-            filled_locations.remove(&removable);
-            for neighbour in neighbours(&removable, &size) {
-                // Update the count of neighbouring cells
-                if let Some(count) = filled_locations.get(&neighbour) {
-                    filled_locations.insert(neighbour, count - 1);
+        let removals: Vec<Removal> = batch
+            .iter()
+            .map(|coord| Removal {
+                coord: *coord,
+                filled_neighbours: filled_locations[coord],
+            })
+            .collect();
+        for coord in batch {
+            filled_locations.remove(&coord);
+            removable.remove(&(coord.y, coord.x));
+            for neighbour in neighbours_of(&coord, &size, &rules.topology) {
+                // Update the count of cells that had this one as a neighbour
+                if let Some(count) = filled_locations.get_mut(&neighbour) {
+                    *count -= 1;
+                    if *count < rules.threshold {
+                        removable.insert((neighbour.y, neighbour.x));
+                    }
                 }
             }
-            if print {
-                set_state_at(removable, State::Taken, &mut grid);
-            }
         }
+        rounds.push(removals);
     }
-    if print {
-        for line in grid {
-            let line_str: String = line.iter().map(state_to_char).collect();
-            println!("{line_str}")
-        }
+    ErosionHistory {
+        initial: grid.to_vec(),
+        rounds,
     }
-    initial_filled - filled_locations.len()
 }
 
-fn filled_location_with_num_filled_neighbours(grid: &Vec<Vec<State>>) -> HashMap<Coord, usize> {
+fn filled_location_with_num_filled_neighbours(
+    grid: &[Vec<State>],
+    rules: &ErosionRules,
+) -> HashMap<Coord, usize> {
     let size = size(grid);
     (0..size.y)
         .flat_map(|y| (0..size.x).map(move |x| Coord { x, y }))
-        .filter(|c| state_at(c, &grid) == State::Filled)
+        .filter(|c| state_at(c, grid) == State::Filled)
         .map(|c| {
             (
                 c,
-                states_of_neighbour_coords(&c, &grid)
+                states_of_neighbour_coords(&c, grid, &rules.topology)
                     .iter()
                     .filter(|&&s| s == State::Filled)
                     .count(),
@@ -94,51 +681,95 @@ fn state_to_char(s: &State) -> char {
     }
 }
 
-fn state_at(coord: &Coord, grid: &Vec<Vec<State>>) -> State {
+fn state_at<S: Copy>(coord: &Coord, grid: &[Vec<S>]) -> S {
     grid[coord.y as usize][coord.x as usize]
 }
 
-fn set_state_at(coord: Coord, state: State, grid: &mut Vec<Vec<State>>) {
+fn set_state_at<S>(coord: Coord, state: S, grid: &mut [Vec<S>]) {
     grid[coord.y as usize][coord.x as usize] = state
 }
 
-fn states_of_neighbour_coords(loc: &Coord, grid: &Vec<Vec<State>>) -> Vec<State> {
-    neighbours(loc, &size(grid))
+fn states_of_neighbour_coords(loc: &Coord, grid: &[Vec<State>], topology: &Topology) -> Vec<State> {
+    neighbours(loc, &size(grid), topology)
         .iter()
-        .map(|n| state_at(n, &grid))
+        .map(|n| state_at(n, grid))
         .collect()
 }
 
-fn size(grid: &Vec<Vec<State>>) -> Coord {
+fn size<S>(grid: &[Vec<S>]) -> Coord {
     Coord {
         x: grid[0].len() as i32,
         y: grid.len() as i32,
     }
 }
 
-fn neighbours(loc: &Coord, grid_size: &Coord) -> Vec<Coord> {
+fn neighbours(loc: &Coord, grid_size: &Coord, topology: &Topology) -> Vec<Coord> {
     let mut neighbours = Vec::new();
-    for dx in [-1, 0, 1] {
-        for dy in [-1, 0, 1] {
-            let potential = Coord {
-                x: loc.x + dx,
-                y: loc.y + dy,
-            };
-            if 0 <= potential.x
-                && potential.x < grid_size.x
-                && 0 <= potential.y
-                && potential.y < grid_size.y
-                && potential != *loc
-            {
-                neighbours.push(potential);
-            }
+    for offset in topology.offsets(loc) {
+        let mut potential = Coord {
+            x: loc.x + offset.x,
+            y: loc.y + offset.y,
+        };
+        if topology.edges == Edges::Toroidal {
+            potential.x = potential.x.rem_euclid(grid_size.x);
+            potential.y = potential.y.rem_euclid(grid_size.y);
+        }
+        // On a small torus several offsets can wrap onto the same cell, or back onto `loc`
+        if 0 <= potential.x
+            && potential.x < grid_size.x
+            && 0 <= potential.y
+            && potential.y < grid_size.y
+            && potential != *loc
+            && !neighbours.contains(&potential)
+        {
+            neighbours.push(potential);
         }
     }
     neighbours
 }
 
-fn parse_line(line: &str) -> Vec<State> {
-    line.chars().map(parse_location).collect()
+/// The cells that have `loc` as one of their neighbours. Only the same as `neighbours` when
+/// the neighbourhood is symmetric, which custom ones needn't be, and hexagonal ones aren't on
+/// a torus with an odd number of rows.
+fn neighbours_of(loc: &Coord, grid_size: &Coord, topology: &Topology) -> Vec<Coord> {
+    // Offsets only depend on whether the row is odd or even, so these are all of them
+    let offsets = topology
+        .offsets(&Coord { x: 0, y: 0 })
+        .into_iter()
+        .chain(topology.offsets(&Coord { x: 0, y: 1 }));
+    let mut cells = Vec::new();
+    for offset in offsets {
+        let mut potential = Coord {
+            x: loc.x - offset.x,
+            y: loc.y - offset.y,
+        };
+        if topology.edges == Edges::Toroidal {
+            potential.x = potential.x.rem_euclid(grid_size.x);
+            potential.y = potential.y.rem_euclid(grid_size.y);
+        }
+        if 0 <= potential.x
+            && potential.x < grid_size.x
+            && 0 <= potential.y
+            && potential.y < grid_size.y
+            && !cells.contains(&potential)
+            && neighbours(&potential, grid_size, topology).contains(loc)
+        {
+            cells.push(potential);
+        }
+    }
+    cells
+}
+
+fn parse_grid(lines: &[String]) -> Vec<Vec<State>> {
+    parse_grid_with(lines, parse_location)
+}
+
+fn parse_grid_with<S>(lines: &[String], parse_cell: impl Fn(char) -> S + Copy) -> Vec<Vec<S>> {
+    lines.iter().map(|l| parse_line(l, parse_cell)).collect()
+}
+
+fn parse_line<S>(line: &str, parse_cell: impl Fn(char) -> S) -> Vec<S> {
+    line.chars().map(parse_cell).collect()
 }
 
 fn parse_location(c: char) -> State {
@@ -152,10 +783,16 @@ fn parse_location(c: char) -> State {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc25::PseudoRandom;
+    use rstest::rstest;
 
     #[test]
     fn test_neighbours() {
-        let output = neighbours(&Coord { x: 1, y: 1 }, &Coord { x: 10, y: 10 });
+        let output = neighbours(
+            &Coord { x: 1, y: 1 },
+            &Coord { x: 10, y: 10 },
+            &Topology::default(),
+        );
         let expected = [
             Coord { x: 0, y: 0 },
             Coord { x: 0, y: 1 },
@@ -168,6 +805,295 @@ mod tests {
         ];
         assert_eq!(output, expected)
     }
+    #[rstest]
+    #[case(Neighbourhood::VonNeumann, Edges::Bounded, &[(0, 1), (1, 0), (1, 2), (2, 1)])]
+    #[case(Neighbourhood::Hexagonal, Edges::Bounded, &[(0, 1), (1, 0), (1, 2), (2, 0), (2, 1), (2, 2)])]
+    #[case(Neighbourhood::Custom(vec![Coord { x: 2, y: 0 }, Coord { x: 0, y: -3 }]), Edges::Bounded, &[(3, 1)])]
+    #[case(Neighbourhood::Custom(vec![Coord { x: 2, y: 0 }, Coord { x: 0, y: -3 }]), Edges::Toroidal, &[(3, 1), (1, 2)])]
+    fn test_neighbourhoods(
+        #[case] neighbourhood: Neighbourhood,
+        #[case] edges: Edges,
+        #[case] expected: &[(i32, i32)],
+    ) {
+        let topology = Topology {
+            neighbourhood,
+            edges,
+        };
+        let output = neighbours(&Coord { x: 1, y: 1 }, &Coord { x: 4, y: 4 }, &topology);
+        let expected: Vec<Coord> = expected.iter().map(|&(x, y)| Coord { x, y }).collect();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_toroidal_corner_has_all_moore_neighbours() {
+        let topology = Topology {
+            edges: Edges::Toroidal,
+            ..Default::default()
+        };
+        let output = neighbours(&Coord { x: 0, y: 0 }, &Coord { x: 5, y: 5 }, &topology);
+        assert_eq!(output.len(), 8);
+        assert!(output.contains(&Coord { x: 4, y: 4 }));
+    }
+
+    #[test]
+    fn test_one_at_a_time_erosion() {
+        // In a 3x2 block the corners have 3 neighbours and the middles 5. Synchronously the
+        // corners go together, then the middles. One at a time, removing the top left corner
+        // leaves the top middle with 4, so the top right corner goes next.
+        let grid = parse_grid(&["@@@".to_string(), "@@@".to_string()]);
+        let sync = erode(&grid, &ErosionRules::default());
+        assert_eq!(sync.rounds.len(), 2);
+        let rules = ErosionRules {
+            update: UpdateMode::OneAtATime,
+            ..Default::default()
+        };
+        let one_at_a_time = erode(&grid, &rules);
+        assert_eq!(one_at_a_time.removed_count(), 6);
+        assert!(one_at_a_time.rounds.iter().all(|round| round.len() == 1));
+        let order: Vec<Removal> = one_at_a_time.rounds.iter().map(|round| round[0]).collect();
+        assert_eq!(
+            order[..3],
+            [
+                Removal {
+                    coord: Coord { x: 0, y: 0 },
+                    filled_neighbours: 3
+                },
+                Removal {
+                    coord: Coord { x: 2, y: 0 },
+                    filled_neighbours: 3
+                },
+                Removal {
+                    coord: Coord { x: 1, y: 0 },
+                    filled_neighbours: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_erosion_history() {
+        let lines: Vec<String> = ["@@@.", "@@@.", "@@@@", "...."]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        let history = erode(&parse_grid(&lines), &ErosionRules::default());
+        assert_eq!(
+            history.rounds[0],
+            [
+                Removal {
+                    coord: Coord { x: 0, y: 0 },
+                    filled_neighbours: 3
+                },
+                Removal {
+                    coord: Coord { x: 2, y: 0 },
+                    filled_neighbours: 3
+                },
+                Removal {
+                    coord: Coord { x: 0, y: 2 },
+                    filled_neighbours: 3
+                },
+                Removal {
+                    coord: Coord { x: 3, y: 2 },
+                    filled_neighbours: 2
+                },
+            ]
+        );
+        assert_eq!(history.removed_count(), 10);
+        let frames = history.ascii_frames();
+        assert_eq!(frames.len(), history.rounds.len() + 1);
+        assert_eq!(frames[0], "@@@.\n@@@.\n@@@@\n....");
+        assert_eq!(frames[1], "x@x.\n@@@.\nx@@x\n....");
+        assert_eq!(frames.last().unwrap(), "xxx.\nxxx.\nxxxx\n....");
+    }
+
+    #[test]
+    fn test_bit_grid() {
+        let lines = vec!["@.@".to_string(), ".@@".to_string()];
+        let mut grid = BitGrid::parse(&lines);
+        assert!(grid.get(0, 0) && !grid.get(1, 0) && grid.get(2, 1));
+        assert_eq!(grid.count_filled(), 4);
+        assert_eq!(grid.filled_neighbours(1, 1), 3);
+        grid.clear(2, 1);
+        assert_eq!(grid.filled_neighbours(1, 1), 2);
+        // Rows wider than a word
+        let wide = vec!["@".repeat(130)];
+        assert_eq!(BitGrid::parse(&wide).count_filled(), 130);
+        assert_eq!(BitGrid::parse(&wide).filled_neighbours(64, 0), 2);
+    }
+
+    /// A grid where each cell is filled with a chance of `density` percent
+    fn random_grid(
+        random: &mut PseudoRandom,
+        width: usize,
+        height: usize,
+        density: u64,
+    ) -> Vec<String> {
+        (0..height)
+            .map(|_| {
+                (0..width)
+                    .map(|_| {
+                        if random.below(100) < density {
+                            '@'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_peel_matches_erosion() {
+        // Pseudo-random grids of varying density
+        let mut random = PseudoRandom::new(12345);
+        for density in [30, 50, 70, 85] {
+            let lines = random_grid(&mut random, 70, 40, density);
+            let expected = erode(&parse_grid(&lines), &ErosionRules::default()).removed_count();
+            assert_eq!(solve_part_b(&lines), expected);
+        }
+    }
+
+    /// Erosion done the slow way, recounting every cell's neighbours each round
+    fn naive_erosion_rounds(grid: &[Vec<State>], rules: &ErosionRules) -> Vec<Vec<Removal>> {
+        let mut grid = grid.to_vec();
+        let mut rounds = Vec::new();
+        loop {
+            let mut round: Vec<Removal> = filled_location_with_num_filled_neighbours(&grid, rules)
+                .into_iter()
+                .filter(|(_, filled_neighbours)| *filled_neighbours < rules.threshold)
+                .map(|(coord, filled_neighbours)| Removal {
+                    coord,
+                    filled_neighbours,
+                })
+                .collect();
+            round.sort_by_key(|removal| (removal.coord.y, removal.coord.x));
+            if round.is_empty() {
+                return rounds;
+            }
+            for removal in &round {
+                grid[removal.coord.y as usize][removal.coord.x as usize] = State::Empty;
+            }
+            rounds.push(round);
+        }
+    }
+
+    #[rstest]
+    #[case(Neighbourhood::Custom(vec![Coord { x: 1, y: 0 }]), Edges::Bounded, 1, 2)]
+    #[case(Neighbourhood::Custom(vec![Coord { x: 2, y: 0 }, Coord { x: 0, y: -3 }]), Edges::Bounded, 1, 4)]
+    #[case(Neighbourhood::Custom(vec![Coord { x: 2, y: 0 }, Coord { x: 0, y: -3 }]), Edges::Toroidal, 2, 4)]
+    #[case(Neighbourhood::Custom(vec![Coord { x: 1, y: 1 }, Coord { x: 1, y: 0 }, Coord { x: -2, y: 0 }]), Edges::Toroidal, 2, 5)]
+    #[case(Neighbourhood::Hexagonal, Edges::Toroidal, 4, 7)]
+    #[case(Neighbourhood::Hexagonal, Edges::Bounded, 3, 7)]
+    fn test_erosion_matches_recounting(
+        #[case] neighbourhood: Neighbourhood,
+        #[case] edges: Edges,
+        #[case] threshold: usize,
+        #[case] height: usize,
+    ) {
+        let rules = ErosionRules {
+            topology: Topology {
+                neighbourhood,
+                edges,
+            },
+            threshold,
+            update: UpdateMode::Synchronous,
+        };
+        assert_eq!(
+            erode(&parse_grid(&["@@".to_string()]), &rules).rounds,
+            naive_erosion_rounds(&parse_grid(&["@@".to_string()]), &rules)
+        );
+        let mut random = PseudoRandom::new(54321);
+        for _ in 0..20 {
+            let lines = random_grid(&mut random, 9, height, 70);
+            let grid = parse_grid(&lines);
+            assert_eq!(
+                erode(&grid, &rules).rounds,
+                naive_erosion_rounds(&grid, &rules),
+                "{lines:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_life_rule() {
+        let rule = parse_life_rule("B3/S23").unwrap();
+        assert_eq!(
+            rule,
+            LifeRule {
+                birth: vec![3],
+                survival: vec![2, 3]
+            }
+        );
+        assert_eq!(parse_life_rule("s23/b36").unwrap().birth, [3, 6]);
+        assert!(parse_life_rule("B3").is_err());
+        assert!(parse_life_rule("B3/X23").is_err());
+    }
+
+    fn life(lines: &[&str]) -> Automaton<State, LifeRule> {
+        let lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        let rule = parse_life_rule("B3/S23").unwrap();
+        Automaton::new(parse_grid(&lines), rule, Topology::default())
+    }
+
+    #[test]
+    fn test_life_block_is_stable() {
+        let mut automaton = life(&["....", ".@@.", ".@@.", "...."]);
+        assert_eq!(automaton.run(None), RunOutcome::Stable { generation: 0 });
+        assert_eq!(automaton.live_count(), 4);
+    }
+
+    #[test]
+    fn test_life_blinker_cycles() {
+        let mut automaton = life(&[".....", "..@..", "..@..", "..@..", "....."]);
+        automaton.step();
+        assert_eq!(
+            state_at(&Coord { x: 1, y: 2 }, &automaton.grid),
+            State::Filled
+        );
+        assert_eq!(
+            state_at(&Coord { x: 2, y: 1 }, &automaton.grid),
+            State::Empty
+        );
+        assert_eq!(
+            automaton.run(None),
+            RunOutcome::Cycle {
+                generation: 1,
+                period: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_life_generation_limit() {
+        // A glider keeps moving until it hits the edge of the grid
+        let mut automaton = life(&[".@......", "..@.....", "@@@.....", "........", "........"]);
+        assert_eq!(automaton.run(Some(3)), RunOutcome::Unsettled);
+        assert_eq!(automaton.generation, 3);
+    }
+
+    #[test]
+    fn test_erosion_rule_matches_erosion() {
+        let lines: Vec<String> = ["@@@.@", "@@@.@", "@@@@@", ".@@@.", "@.@.@"]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        let history = erode(&parse_grid(&lines), &ErosionRules::default());
+        let mut automaton = Automaton::new(
+            parse_grid(&lines),
+            ErosionRule { threshold: 4 },
+            Topology::default(),
+        );
+        let outcome = automaton.run(None);
+        assert_eq!(
+            outcome,
+            RunOutcome::Stable {
+                generation: history.rounds.len()
+            }
+        );
+        assert_eq!(automaton.grid, history.grid_after(history.rounds.len()));
+    }
+
     #[test]
     fn test_part_a() {
         assert_eq!(13, solve_part_a(&load_input(DAY, Input::Test)));
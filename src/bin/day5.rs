@@ -1,16 +1,342 @@
+use anyhow::{Context, Result, bail};
 use aoc25::{Input, load_input};
 use itertools::any;
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
 use std::slice::Iter;
+use std::sync::Arc;
+use std::thread;
 const DAY: u8 = 5;
 
+/// The fresh ID ranges, merged once so that lookups are a binary search
+struct RangeIndex {
+    /// The ranges as given, sorted by start
+    ranges: Vec<(u64, u64)>,
+    merged: Vec<(u64, u64)>,
+}
+
+impl RangeIndex {
+    fn new(mut ranges: Vec<(u64, u64)>) -> RangeIndex {
+        ranges.sort();
+        let merged = combine_ranges(ranges.clone());
+        RangeIndex { ranges, merged }
+    }
+
+    fn contains(&self, id: u64) -> bool {
+        let idx = self.merged.partition_point(|range| range.1 < id);
+        self.merged.get(idx).is_some_and(|range| range.0 <= id)
+    }
+
+    /// Every original range containing `id`
+    fn matching_ranges(&self, id: u64) -> Vec<(u64, u64)> {
+        if !self.contains(id) {
+            return Vec::new();
+        }
+        let started = self.ranges.partition_point(|range| range.0 <= id);
+        self.ranges[..started]
+            .iter()
+            .filter(|range| range.1 >= id)
+            .copied()
+            .collect()
+    }
+
+    /// How many IDs in `lower..=upper` aren't in any range
+    fn free_between(&self, lower: u64, upper: u64) -> u128 {
+        if lower > upper {
+            return 0;
+        }
+        let covered: u128 = self
+            .merged
+            .iter()
+            .filter(|range| range.0 <= upper && range.1 >= lower)
+            .map(|range| (range.1.min(upper) - range.0.max(lower)) as u128 + 1)
+            .sum();
+        (upper - lower) as u128 + 1 - covered
+    }
+
+    /// Answer one query line: either an ID, or `free LOWER UPPER`
+    fn answer(&self, query: &str) -> String {
+        self.try_answer(query)
+            .unwrap_or_else(|err| format!("error: {err:#}"))
+    }
+
+    fn try_answer(&self, query: &str) -> Result<String> {
+        let parts: Vec<&str> = query.split_whitespace().collect();
+        let parse = |s: &str| {
+            s.parse::<u64>()
+                .with_context(|| format!("Couldn't parse {s}"))
+        };
+        match parts[..] {
+            [id] => {
+                let id = parse(id)?;
+                let matching = self.matching_ranges(id);
+                if matching.is_empty() {
+                    Ok(format!("{id} spoiled"))
+                } else {
+                    let ranges: Vec<String> =
+                        matching.iter().map(|(a, b)| format!("{a}-{b}")).collect();
+                    Ok(format!("{id} fresh in {}", ranges.join(", ")))
+                }
+            }
+            ["free", lower, upper] => {
+                let (lower, upper) = (parse(lower)?, parse(upper)?);
+                Ok(format!(
+                    "free {lower}-{upper}: {}",
+                    self.free_between(lower, upper)
+                ))
+            }
+            _ => bail!("Expected an ID or `free LOWER UPPER`"),
+        }
+    }
+}
+
+/// IDs that changed state in one update to a `RangeSet`
+#[derive(Debug, PartialEq, Eq)]
+struct RangeChange {
+    /// Whether the IDs became fresh, or stopped being fresh
+    fresh: bool,
+    ranges: Vec<(u64, u64)>,
+}
+
+/// Fresh ID ranges that can be added to and taken away from over time, keeping the total
+/// number of fresh IDs up to date. Ranges are kept disjoint and non-adjacent, keyed by start.
+///
+/// Adding or removing a range costs O(log n) to find where it goes plus the number of stored
+/// ranges it touches. Each update creates at most two new stored ranges, so that's O(log n)
+/// amortised.
+#[derive(Debug, Default)]
+struct RangeSet {
+    ranges: BTreeMap<u64, u64>,
+    total: u128,
+}
+
+impl RangeSet {
+    fn contains(&self, id: u64) -> bool {
+        self.ranges
+            .range(..=id)
+            .next_back()
+            .is_some_and(|(_, &end)| end >= id)
+    }
+
+    /// Stored ranges that overlap `start..=end`, or touch it if `adjacent`, in order
+    fn touching(&self, start: u64, end: u64, adjacent: bool) -> Vec<(u64, u64)> {
+        let slack = adjacent as u64;
+        let mut found: Vec<(u64, u64)> = self
+            .ranges
+            .range(..=end.saturating_add(slack))
+            .rev()
+            .take_while(|(_, range_end)| range_end.saturating_add(slack) >= start)
+            .map(|(&s, &e)| (s, e))
+            .collect();
+        found.reverse();
+        found
+    }
+
+    /// Make every ID in `start..=end` fresh, returning the IDs that weren't already
+    fn insert(&mut self, start: u64, end: u64) -> RangeChange {
+        let touching = self.touching(start, end, true);
+        let newly_fresh = subtract_ranges(&[(start, end)], &touching);
+        let merged_start = touching.first().map_or(start, |range| range.0.min(start));
+        let merged_end = touching.last().map_or(end, |range| range.1.max(end));
+        for (range_start, _) in &touching {
+            self.ranges.remove(range_start);
+        }
+        self.ranges.insert(merged_start, merged_end);
+        self.total += range_sizes(&newly_fresh);
+        RangeChange {
+            fresh: true,
+            ranges: newly_fresh,
+        }
+    }
+
+    /// Make every ID in `start..=end` spoiled, returning the IDs that were fresh
+    fn remove(&mut self, start: u64, end: u64) -> RangeChange {
+        let overlapping = self.touching(start, end, false);
+        let mut newly_spoiled = Vec::new();
+        for (range_start, range_end) in overlapping {
+            self.ranges.remove(&range_start);
+            if range_start < start {
+                self.ranges.insert(range_start, start - 1);
+            }
+            if range_end > end {
+                self.ranges.insert(end + 1, range_end);
+            }
+            newly_spoiled.push((range_start.max(start), range_end.min(end)));
+        }
+        self.total -= range_sizes(&newly_spoiled);
+        RangeChange {
+            fresh: false,
+            ranges: newly_spoiled,
+        }
+    }
+
+    /// Apply an update line: `+RANGE` to add or `-RANGE` to remove, in any range syntax
+    fn apply(&mut self, update: &str) -> Result<RangeChange> {
+        let (adding, range) = match update.split_at_checked(1) {
+            Some(("+", range)) => (true, range),
+            Some(("-", range)) => (false, range),
+            _ => bail!("Expected +RANGE or -RANGE"),
+        };
+        let Some((start, end)) = parse_range(range)? else {
+            return Ok(RangeChange {
+                fresh: adding,
+                ranges: Vec::new(),
+            });
+        };
+        Ok(if adding {
+            self.insert(start, end)
+        } else {
+            self.remove(start, end)
+        })
+    }
+}
+
+fn range_sizes(ranges: &[(u64, u64)]) -> u128 {
+    ranges
+        .iter()
+        .map(|range| (range.1 - range.0) as u128 + 1)
+        .sum()
+}
+
 fn main() {
     let lines = load_input(DAY, Input::Puzzle);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--updates") {
+        let (ranges, _) = parse_input(lines.iter());
+        let mut range_set = RangeSet::default();
+        for (start, end) in ranges {
+            range_set.insert(start, end);
+        }
+        println!("Starting with {} fresh IDs", range_set.total);
+        apply_updates(&mut range_set, io::stdin().lock(), io::stdout().lock())
+            .expect("Couldn't apply updates");
+        return;
+    }
+    if let Some(address) = args.iter().skip_while(|arg| *arg != "--serve").nth(1) {
+        let (ranges, _) = parse_input(lines.iter());
+        let index = Arc::new(RangeIndex::new(ranges));
+        serve(index, address).expect("Lookup service failed");
+        return;
+    }
     let a = solve_part_a(&lines);
     println!("Solution to a: {a}");
     let b = solve_part_b(&lines);
     println!("Solution to b: {b}");
 }
 
+/// Answer queries from `stdin`, `tcp:HOST:PORT` or `unix:PATH`, one per line
+fn serve(index: Arc<RangeIndex>, address: &str) -> Result<()> {
+    if address == "stdin" {
+        return answer_queries(&index, io::stdin().lock(), io::stdout().lock());
+    }
+    if let Some(tcp_address) = address.strip_prefix("tcp:") {
+        let listener = TcpListener::bind(tcp_address)?;
+        println!("Listening on {}", listener.local_addr()?);
+        return serve_connections(index, listener.incoming(), TcpStream::try_clone);
+    }
+    if let Some(path) = address.strip_prefix("unix:") {
+        return serve_unix(index, path);
+    }
+    bail!("Unknown address {address}, expected stdin, tcp:HOST:PORT or unix:PATH")
+}
+
+#[cfg(unix)]
+fn serve_unix(index: Arc<RangeIndex>, path: &str) -> Result<()> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+    let listener = UnixListener::bind(path)?;
+    println!("Listening on {path}");
+    serve_connections(index, listener.incoming(), UnixStream::try_clone)
+}
+
+#[cfg(not(unix))]
+fn serve_unix(_index: Arc<RangeIndex>, _path: &str) -> Result<()> {
+    bail!("Unix sockets aren't supported on this platform")
+}
+
+/// Answer queries on each incoming connection in its own thread. `try_clone` gives a second
+/// handle to the stream, so that one can be read while the other's written to.
+fn serve_connections<S: Read + Write + Send + 'static>(
+    index: Arc<RangeIndex>,
+    incoming: impl Iterator<Item = io::Result<S>>,
+    try_clone: fn(&S) -> io::Result<S>,
+) -> Result<()> {
+    for stream in incoming {
+        // One failed connection shouldn't stop the others being served
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("Couldn't accept connection: {err}");
+                continue;
+            }
+        };
+        let index = Arc::clone(&index);
+        thread::spawn(move || {
+            let result = try_clone(&stream)
+                .map_err(anyhow::Error::from)
+                .and_then(|reader| answer_queries(&index, BufReader::new(reader), stream));
+            if let Err(err) = result {
+                eprintln!("Connection failed: {err:#}");
+            }
+        });
+    }
+    Ok(())
+}
+
+fn answer_queries(index: &RangeIndex, reader: impl BufRead, mut writer: impl Write) -> Result<()> {
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        writeln!(writer, "{}", index.answer(&line))?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Apply update lines to the range set, writing out the IDs that changed and the new total.
+/// A line `?ID` checks whether an ID is currently fresh.
+fn apply_updates(
+    range_set: &mut RangeSet,
+    reader: impl BufRead,
+    mut writer: impl Write,
+) -> Result<()> {
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(id) = line.trim().strip_prefix('?') {
+            match id.parse() {
+                Ok(id) if range_set.contains(id) => writeln!(writer, "{id} fresh")?,
+                Ok(id) => writeln!(writer, "{id} spoiled")?,
+                Err(err) => writeln!(writer, "error: Couldn't parse {id}: {err}")?,
+            }
+            continue;
+        }
+        match range_set.apply(line.trim()) {
+            Ok(change) => {
+                let ranges: Vec<String> = change
+                    .ranges
+                    .iter()
+                    .map(|(a, b)| format!("{a}-{b}"))
+                    .collect();
+                let state = if change.fresh { "fresh" } else { "spoiled" };
+                writeln!(
+                    writer,
+                    "{state}: [{}] total: {}",
+                    ranges.join(", "),
+                    range_set.total
+                )?;
+            }
+            Err(err) => writeln!(writer, "error: {err:#}")?,
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
 fn solve_part_a(lines: &[String]) -> i32 {
     let (ranges, ids) = parse_input(lines.iter());
     let mut count = 0;
@@ -22,11 +348,12 @@ fn solve_part_a(lines: &[String]) -> i32 {
     count
 }
 
-fn solve_part_b(lines: &[String]) -> u64 {
+fn solve_part_b(lines: &[String]) -> u128 {
     let (ranges, _) = parse_input(lines.iter());
     let mut count = 0;
     for range in combine_ranges(ranges) {
-        count += range.1 - range.0 + 1;
+        // An open-ended range from 0 covers 2^64 IDs, one more than fits in a u64
+        count += (range.1 - range.0) as u128 + 1;
     }
     count
 }
@@ -58,42 +385,273 @@ fn combine_ranges(ranges: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
     combined_ranges
 }
 
-fn contained_in_any(comparison: (u64, u64), ranges: &Vec<(u64, u64)>) -> bool {
+fn contained_in_any(comparison: (u64, u64), ranges: &[(u64, u64)]) -> bool {
     any(ranges, |range| {
         comparison.0 >= range.0 && comparison.1 <= range.1
     })
 }
 
+/// Take away every ID in `excluded` from `included`. Both must be sorted and non-overlapping.
+fn subtract_ranges(included: &[(u64, u64)], excluded: &[(u64, u64)]) -> Vec<(u64, u64)> {
+    let mut remaining = Vec::new();
+    for &(start, end) in included {
+        // The first ID not yet accounted for, or None once past u64::MAX
+        let mut next = Some(start);
+        for &(ex_start, ex_end) in excluded.iter().filter(|ex| ex.0 <= end && ex.1 >= start) {
+            if let Some(from) = next
+                && from < ex_start
+            {
+                remaining.push((from, ex_start - 1));
+            }
+            next = ex_end.checked_add(1);
+        }
+        if let Some(from) = next
+            && from <= end
+        {
+            remaining.push((from, end));
+        }
+    }
+    remaining
+}
+
+/// The ranges, after taking away any exclusions, and the IDs
 fn parse_input(lines: Iter<String>) -> (Vec<(u64, u64)>, Vec<u64>) {
-    let mut ranges = Vec::new();
+    let mut included = Vec::new();
+    let mut excluded = Vec::new();
     let mut ids = Vec::new();
     let mut finished_ranges = false;
     for line in lines {
-        if line.len() == 0 as usize {
+        if line.is_empty() {
             finished_ranges = true;
             continue;
         }
         if finished_ranges {
             ids.push(line.parse().expect("Couldn't parse id line"));
+        } else if let Some(exclusion) = line.strip_prefix('!') {
+            excluded.extend(parse_range(exclusion).expect("Couldn't parse range"));
         } else {
-            ranges.push(parse_range(line));
+            included.extend(parse_range(line).expect("Couldn't parse range"));
         }
     }
+    let ranges = subtract_ranges(&combine_ranges(included), &combine_ranges(excluded));
     (ranges, ids)
 }
 
-fn parse_range(line: &str) -> (u64, u64) {
-    let (lower, upper) = line.split_once('-').expect("Couldn't split range line");
-    let msg = "Couldn't parse range";
-    (
-        lower.to_string().parse().expect(msg),
-        upper.to_string().parse().expect(msg),
-    )
+/// Parse an inclusive range from `a-b`, a half-open range from `a..b`, a single value `a`,
+/// or an open-ended range `a-` which runs to the largest ID. Empty half-open ranges like
+/// `a..a` give `None`, but ranges that end before they start are an error either way.
+fn parse_range(line: &str) -> Result<Option<(u64, u64)>> {
+    let parse = |s: &str| -> Result<u64> {
+        s.trim()
+            .parse()
+            .with_context(|| format!("Couldn't parse {s} in range {line}"))
+    };
+    if let Some((lower, upper)) = line.split_once("..") {
+        let (lower, upper) = (parse(lower)?, parse(upper)?);
+        if lower > upper {
+            bail!("Range {line} ends before it starts");
+        }
+        return Ok((lower < upper).then(|| (lower, upper - 1)));
+    }
+    let range = match line.split_once('-') {
+        Some((lower, "")) => (parse(lower)?, u64::MAX),
+        Some((lower, upper)) => (parse(lower)?, parse(upper)?),
+        None => (parse(line)?, parse(line)?),
+    };
+    if range.0 > range.1 {
+        bail!("Range {line} ends before it starts");
+    }
+    Ok(Some(range))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc25::PseudoRandom;
+    use rstest::rstest;
+    use std::io::Cursor;
+
+    fn example_index() -> RangeIndex {
+        RangeIndex::new(vec![(3, 5), (10, 14), (16, 20), (12, 18)])
+    }
+
+    #[test]
+    fn test_range_index_lookups() {
+        let index = example_index();
+        assert!(!index.contains(1) && index.contains(5) && !index.contains(8));
+        assert_eq!(index.matching_ranges(11), [(10, 14)]);
+        assert_eq!(index.matching_ranges(17), [(12, 18), (16, 20)]);
+        assert_eq!(index.matching_ranges(32), []);
+        assert_eq!(index.free_between(1, 32), 32 - 14);
+        assert_eq!(index.free_between(6, 9), 4);
+        assert_eq!(index.free_between(14, 16), 0);
+        assert_eq!(index.free_between(0, u64::MAX), (u64::MAX - 14) as u128 + 1);
+        assert_eq!(
+            RangeIndex::new(vec![(0, u64::MAX)]).free_between(0, u64::MAX),
+            0
+        );
+        assert_eq!(
+            index.answer("free 0 18446744073709551615"),
+            format!("free 0-{}: {}", u64::MAX, (u64::MAX - 14) as u128 + 1)
+        );
+    }
+
+    #[test]
+    fn test_answer_queries() {
+        let queries = "5\n8\n\n17\nfree 1 12\nfree x 2\n";
+        let mut output = Vec::new();
+        answer_queries(&example_index(), Cursor::new(queries), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "5 fresh in 3-5\n\
+             8 spoiled\n\
+             17 fresh in 12-18, 16-20\n\
+             free 1-12: 6\n\
+             error: Couldn't parse x: invalid digit found in string\n"
+        );
+    }
+
+    #[test]
+    fn test_serve_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let index = Arc::new(example_index());
+        thread::spawn(move || serve_connections(index, listener.incoming(), TcpStream::try_clone));
+        // Each connection gets its own answers
+        for (queries, expected) in [
+            ("5\nfree 1 12\n", "5 fresh in 3-5\nfree 1-12: 6\n"),
+            ("\n8\n17\n", "8 spoiled\n17 fresh in 12-18, 16-20\n"),
+        ] {
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream.write_all(queries.as_bytes()).unwrap();
+            stream.shutdown(std::net::Shutdown::Write).unwrap();
+            let mut replies = String::new();
+            stream.read_to_string(&mut replies).unwrap();
+            assert_eq!(replies, expected);
+        }
+    }
+
+    #[rstest]
+    #[case("3-5", Some((3, 5)))]
+    #[case("3..5", Some((3, 4)))]
+    #[case("5..5", None)]
+    #[case("7", Some((7, 7)))]
+    #[case("10-", Some((10, u64::MAX)))]
+    fn test_parse_range(#[case] line: &str, #[case] expected: Option<(u64, u64)>) {
+        assert_eq!(parse_range(line).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case("5-3")]
+    #[case("5..3")]
+    #[case("a-3")]
+    #[case("-3")]
+    fn test_parse_range_errors(#[case] line: &str) {
+        assert!(parse_range(line).is_err());
+    }
+
+    #[test]
+    fn test_subtract_ranges() {
+        assert_eq!(
+            subtract_ranges(&[(1, 10), (20, 30)], &[(0, 2), (5, 6), (10, 21), (30, 40)]),
+            [(3, 4), (7, 9), (22, 29)]
+        );
+        assert_eq!(
+            subtract_ranges(&[(5, u64::MAX)], &[(7, u64::MAX)]),
+            [(5, 6)]
+        );
+    }
+
+    #[test]
+    fn test_extended_syntax() {
+        let lines: Vec<String> = [
+            "3-5", "10..15", "16-20", "12-18", "!13-17", "!4", "30-", "", "4", "5", "13", "18",
+            "31",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+        let (ranges, _) = parse_input(lines.iter());
+        assert_eq!(ranges, [(3, 3), (5, 5), (10, 12), (18, 20), (30, u64::MAX)]);
+        assert_eq!(solve_part_a(&lines), 3);
+        assert_eq!(solve_part_b(&lines), 8 + (u64::MAX - 30) as u128 + 1);
+        let everything = vec!["0-".to_string()];
+        assert_eq!(solve_part_b(&everything), 1 << 64);
+    }
+
+    #[test]
+    fn test_range_set_updates() {
+        let mut set = RangeSet::default();
+        assert_eq!(set.insert(10, 14).ranges, [(10, 14)]);
+        assert_eq!(set.insert(3, 5).ranges, [(3, 5)]);
+        assert_eq!(set.insert(12, 18).ranges, [(15, 18)]);
+        assert_eq!(set.insert(6, 9).ranges, [(6, 9)]);
+        assert_eq!(set.ranges.len(), 1);
+        assert_eq!(set.total, 16);
+        let change = set.remove(5, 11);
+        assert_eq!(
+            change,
+            RangeChange {
+                fresh: false,
+                ranges: vec![(5, 11)]
+            }
+        );
+        assert_eq!(set.insert(1, 20).ranges, [(1, 2), (5, 11), (19, 20)]);
+        assert_eq!(set.remove(30, 40).ranges, []);
+        assert_eq!(set.total, 20);
+        assert_eq!(
+            set.insert(u64::MAX - 1, u64::MAX).ranges,
+            [(u64::MAX - 1, u64::MAX)]
+        );
+        assert!(set.contains(u64::MAX) && !set.contains(21));
+        assert_eq!(set.remove(0, u64::MAX).ranges.len(), 2);
+        assert_eq!(set.total, 0);
+    }
+
+    #[test]
+    fn test_range_set_matches_brute_force() {
+        let mut set = RangeSet::default();
+        let mut fresh = [false; 200];
+        let mut random = PseudoRandom::new(99);
+        for _ in 0..2000 {
+            let seed = random.next_u64();
+            let start = (seed >> 20) % 200;
+            let end = (start + (seed >> 40) % 15).min(199);
+            let adding = !(seed >> 10).is_multiple_of(3);
+            let change = if adding {
+                set.insert(start, end)
+            } else {
+                set.remove(start, end)
+            };
+            for &(a, b) in &change.ranges {
+                for id in a..=b {
+                    assert_ne!(fresh[id as usize], adding, "{id} didn't change");
+                    fresh[id as usize] = adding;
+                }
+            }
+            for id in start..=end {
+                assert_eq!(fresh[id as usize], adding);
+            }
+            assert_eq!(set.total, fresh.iter().filter(|f| **f).count() as u128);
+        }
+    }
+
+    #[test]
+    fn test_apply_updates() {
+        let mut set = RangeSet::default();
+        let updates = "+3-5\n+4..8\n-5\n*3\n?4\n?5\n";
+        let mut output = Vec::new();
+        apply_updates(&mut set, Cursor::new(updates), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "fresh: [3-5] total: 3\n\
+             fresh: [6-7] total: 5\n\
+             spoiled: [5-5] total: 4\n\
+             error: Expected +RANGE or -RANGE\n\
+             4 fresh\n\
+             5 spoiled\n"
+        );
+    }
 
     #[test]
     fn test_part_a() {
@@ -1,77 +1,747 @@
+use anyhow::{Context, Result, anyhow, bail, ensure};
 use aoc25::{Input, load_input};
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::Formatter;
+use std::ops::Range;
 
 const DAY: u8 = 6;
 
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+enum Op {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+    Max,
+    Min,
+}
+
+impl Op {
+    const ALL: [Op; 7] = [
+        Op::Add,
+        Op::Subtract,
+        Op::Multiply,
+        Op::Divide,
+        Op::Power,
+        Op::Max,
+        Op::Min,
+    ];
+
+    fn parse(symbol: &str) -> Result<Op> {
+        Op::ALL
+            .into_iter()
+            .find(|op| op.symbol() == symbol)
+            .with_context(|| format!("Unrecognised operator {symbol:?}"))
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            Op::Add => "+",
+            Op::Subtract => "-",
+            Op::Multiply => "*",
+            Op::Divide => "/",
+            Op::Power => "^",
+            Op::Max => "max",
+            Op::Min => "min",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+enum ArithmeticError {
+    Overflow,
+    DivisionByZero,
+}
+
+impl fmt::Display for ArithmeticError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ArithmeticError::Overflow => write!(f, "Overflow"),
+            ArithmeticError::DivisionByZero => write!(f, "Division by zero"),
+        }
+    }
+}
+
+/// A type answers can be worked out in
+trait Number: Sized + fmt::Display {
+    fn from_u64(n: u64) -> Self;
+
+    /// `self op operand`. Division rounds towards zero.
+    fn apply(&self, op: Op, operand: u64) -> Result<Self, ArithmeticError>;
+
+    fn add(&self, other: &Self) -> Result<Self, ArithmeticError>;
+}
+
+impl Number for i128 {
+    fn from_u64(n: u64) -> Self {
+        n as i128
+    }
+
+    fn apply(&self, op: Op, operand: u64) -> Result<Self, ArithmeticError> {
+        let operand = operand as i128;
+        match op {
+            Op::Add => self.checked_add(operand),
+            Op::Subtract => self.checked_sub(operand),
+            Op::Multiply => self.checked_mul(operand),
+            Op::Divide if operand == 0 => return Err(ArithmeticError::DivisionByZero),
+            Op::Divide => self.checked_div(operand),
+            Op::Power => match *self {
+                _ if operand == 0 => Some(1),
+                0 | 1 => Some(*self),
+                -1 => Some(if operand % 2 == 0 { 1 } else { -1 }),
+                _ => u32::try_from(operand)
+                    .ok()
+                    .and_then(|exponent| self.checked_pow(exponent)),
+            },
+            Op::Max => Some(operand.max(*self)),
+            Op::Min => Some(operand.min(*self)),
+        }
+        .ok_or(ArithmeticError::Overflow)
+    }
+
+    fn add(&self, other: &Self) -> Result<Self, ArithmeticError> {
+        self.checked_add(*other).ok_or(ArithmeticError::Overflow)
+    }
+}
+
+/// An arbitrary precision integer, as base 10^9 limbs with the least significant first
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct BigInt {
+    negative: bool,
+    limbs: Vec<u32>,
+}
+
+const LIMB_BASE: u64 = 1_000_000_000;
+/// The most bits a power can have. Any more and squaring takes too long.
+const MAX_POWER_BITS: u64 = 1 << 18;
+
+impl BigInt {
+    fn from_magnitude(negative: bool, mut limbs: Vec<u32>) -> BigInt {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        // Zero is never negative, so it has a single representation
+        BigInt {
+            negative: negative && !limbs.is_empty(),
+            limbs,
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// A slight overestimate of the number of bits in the magnitude, as each limb takes just
+    /// under 30
+    fn bit_length(&self) -> u64 {
+        match self.limbs.last() {
+            Some(top) => (self.limbs.len() as u64 - 1) * 30 + (32 - top.leading_zeros()) as u64,
+            None => 0,
+        }
+    }
+
+    fn negate(&self) -> BigInt {
+        BigInt::from_magnitude(!self.negative, self.limbs.clone())
+    }
+
+    fn multiply(&self, other: &BigInt) -> BigInt {
+        let mut result = vec![0_u64; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let total = result[i + j] + a as u64 * b as u64 + carry;
+                result[i + j] = total % LIMB_BASE;
+                carry = total / LIMB_BASE;
+            }
+            result[i + other.limbs.len()] += carry;
+        }
+        let limbs = result.into_iter().map(|limb| limb as u32).collect();
+        BigInt::from_magnitude(self.negative != other.negative, limbs)
+    }
+
+    /// Divide by a non-zero divisor, rounding towards zero
+    fn divide(&self, divisor: u64) -> BigInt {
+        let mut limbs = vec![0; self.limbs.len()];
+        let mut remainder: u128 = 0;
+        for (i, &limb) in self.limbs.iter().enumerate().rev() {
+            let current = remainder * LIMB_BASE as u128 + limb as u128;
+            limbs[i] = (current / divisor as u128) as u32;
+            remainder = current % divisor as u128;
+        }
+        BigInt::from_magnitude(self.negative, limbs)
+    }
+
+    fn pow(&self, mut exponent: u64) -> BigInt {
+        let mut result = BigInt::from_u64(1);
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent % 2 == 1 {
+                result = result.multiply(&base);
+            }
+            exponent /= 2;
+            if exponent > 0 {
+                base = base.multiply(&base);
+            }
+        }
+        result
+    }
+}
+
+fn compare_magnitudes(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let total = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        result.push((total % LIMB_BASE) as u32);
+        carry = total / LIMB_BASE;
+    }
+    result.push(carry as u32);
+    result
+}
+
+/// `a - b`, where `a` is at least as big as `b`
+fn subtract_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (i, &limb) in a.iter().enumerate() {
+        let subtrahend = *b.get(i).unwrap_or(&0) as i64 + borrow;
+        let mut difference = limb as i64 - subtrahend;
+        borrow = 0;
+        if difference < 0 {
+            difference += LIMB_BASE as i64;
+            borrow = 1;
+        }
+        result.push(difference as u32);
+    }
+    result
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitudes(&self.limbs, &other.limbs),
+            (true, true) => compare_magnitudes(&other.limbs, &self.limbs),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Some((most_significant, rest)) = self.limbs.split_last() else {
+            return write!(f, "0");
+        };
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{most_significant}")?;
+        for limb in rest.iter().rev() {
+            write!(f, "{limb:09}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Number for BigInt {
+    fn from_u64(n: u64) -> Self {
+        let limbs = vec![
+            (n % LIMB_BASE) as u32,
+            ((n / LIMB_BASE) % LIMB_BASE) as u32,
+            (n / LIMB_BASE / LIMB_BASE) as u32,
+        ];
+        BigInt::from_magnitude(false, limbs)
+    }
+
+    fn apply(&self, op: Op, operand: u64) -> Result<Self, ArithmeticError> {
+        let other = BigInt::from_u64(operand);
+        Ok(match op {
+            Op::Add => Number::add(self, &other)?,
+            Op::Subtract => Number::add(self, &other.negate())?,
+            Op::Multiply => self.multiply(&other),
+            Op::Divide if operand == 0 => return Err(ArithmeticError::DivisionByZero),
+            Op::Divide => self.divide(operand),
+            // Powers of anything but 0 and ±1 this large would take forever to work out
+            Op::Power
+                if !(self.is_zero() || self.limbs == [1])
+                    && operand.saturating_mul(self.bit_length()) > MAX_POWER_BITS =>
+            {
+                return Err(ArithmeticError::Overflow);
+            }
+            Op::Power if self.limbs == [1] && operand > 1 => {
+                BigInt::from_magnitude(self.negative && operand % 2 == 1, vec![1])
+            }
+            Op::Power => self.pow(operand),
+            Op::Max => self.clone().max(other),
+            Op::Min => self.clone().min(other),
+        })
+    }
+
+    fn add(&self, other: &Self) -> Result<Self, ArithmeticError> {
+        if self.negative == other.negative {
+            return Ok(BigInt::from_magnitude(
+                self.negative,
+                add_magnitudes(&self.limbs, &other.limbs),
+            ));
+        }
+        Ok(match compare_magnitudes(&self.limbs, &other.limbs) {
+            Ordering::Less => BigInt::from_magnitude(
+                other.negative,
+                subtract_magnitudes(&other.limbs, &self.limbs),
+            ),
+            _ => BigInt::from_magnitude(
+                self.negative,
+                subtract_magnitudes(&self.limbs, &other.limbs),
+            ),
+        })
+    }
+}
+
+/// One problem on the worksheet: the operator applied across its operands from left to right
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Problem {
+    operands: Vec<u64>,
+    op: Op,
+    /// Where the operator is on its line
+    column: usize,
+}
+
+impl Problem {
+    fn evaluate<N: Number>(&self) -> Result<N> {
+        let (first, rest) = self
+            .operands
+            .split_first()
+            .with_context(|| format!("Problem at column {} has no operands", self.column))?;
+        rest.iter()
+            .try_fold(N::from_u64(*first), |total, &operand| {
+                total.apply(self.op, operand)
+            })
+            .map_err(|err| anyhow!("{err} in the problem at column {}: {self}", self.column))
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let operands: Vec<String> = self.operands.iter().map(|n| n.to_string()).collect();
+        write!(f, "{}", operands.join(&format!(" {} ", self.op.symbol())))
+    }
+}
+
 fn main() {
     let lines = load_input(DAY, Input::Puzzle);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let parts = [("a", parse_rows(&lines)), ("b", parse_columns(&lines))];
+    if args.iter().any(|arg| arg == "--show") {
+        for (name, problems) in &parts {
+            println!("Problems for {name}:");
+            for problem in problems.as_ref().expect("Couldn't parse worksheet") {
+                match problem.evaluate::<BigInt>() {
+                    Ok(answer) => println!("  {problem} = {answer}"),
+                    Err(err) => println!("  {problem} = {err}"),
+                }
+            }
+        }
+    }
+    if args.iter().any(|arg| arg == "--big") {
+        for (name, problems) in &parts {
+            let problems = problems.as_ref().expect("Couldn't parse worksheet");
+            let total: BigInt = grand_total(problems).expect("Couldn't work out total");
+            println!("Exact solution to {name}: {total}");
+        }
+        return;
+    }
     let a = solve_part_a(&lines);
     println!("Solution to a: {a}");
     let b = solve_part_b(&lines);
     println!("Solution to b: {b}");
 }
 
-fn solve_part_a(lines: &[String]) -> u64 {
-    let mut line_iters: Vec<_> = lines.iter().map(|l| l.split_whitespace()).collect();
-    let (operations, number_strings) = line_iters
-        .split_last_mut()
-        .expect("Couldn't unwarp list of line iters");
-    let mut sum: u64 = 0;
-    for op in operations {
-        let numbers: Vec<u64> = number_strings
-            .iter_mut()
-            .map(|n| n.next().expect("").parse().unwrap())
-            .collect();
-        let total: u64 = match op {
-            "*" => numbers.iter().product(),
-            "+" => numbers.iter().sum(),
-            _ => panic!("Unrecognised operand"),
-        };
-        sum += total;
+fn solve_part_a(lines: &[String]) -> i128 {
+    let problems = parse_rows(lines).expect("Couldn't parse worksheet");
+    grand_total(&problems).expect("Couldn't work out total")
+}
+
+fn solve_part_b(lines: &[String]) -> i128 {
+    let problems = parse_columns(lines).expect("Couldn't parse worksheet");
+    grand_total(&problems).expect("Couldn't work out total")
+}
+
+/// The sum of every problem's answer
+fn grand_total<N: Number>(problems: &[Problem]) -> Result<N> {
+    problems.iter().try_fold(N::from_u64(0), |total, problem| {
+        total
+            .add(&problem.evaluate()?)
+            .map_err(|err| anyhow!("{err} adding up the answers"))
+    })
+}
+
+/// Read each problem down a column of whitespace separated numbers, with the operator on the
+/// last line
+fn parse_rows(lines: &[String]) -> Result<Vec<Problem>> {
+    let (operations, number_lines) = lines.split_last().context("Worksheet is empty")?;
+    let mut problems: Vec<Problem> = operator_tokens(operations)
+        .map(|(column, symbol)| {
+            Ok(Problem {
+                operands: Vec::new(),
+                op: Op::parse(symbol)?,
+                column,
+            })
+        })
+        .collect::<Result<_>>()?;
+    let num_ops = problems.len();
+    for (row, line) in number_lines.iter().enumerate() {
+        let numbers: Vec<&str> = line.split_whitespace().collect();
+        ensure!(
+            numbers.len() == num_ops,
+            "Row {row} has {} numbers but there are {num_ops} operators",
+            numbers.len(),
+        );
+        for (problem, number) in problems.iter_mut().zip(numbers) {
+            let operand = number
+                .parse()
+                .with_context(|| format!("Couldn't parse {number:?} on row {row}"))?;
+            problem.operands.push(operand);
+        }
     }
-    sum
+    Ok(problems)
 }
 
-fn solve_part_b(lines: &[String]) -> u64 {
-    let mut line_iters: Vec<Vec<char>> = lines.iter().map(|l| l.chars().collect()).collect();
-    let (operations_line, number_strings) = line_iters
-        .split_last_mut()
-        .expect("Couldn't unwarp list of line iters");
-    let mut sum: u64 = 0;
-    let mut total = 0;
-    let mut current_op = ' ';
-    let max_length = number_strings
+/// Read the worksheet right to left a column at a time. Problems are blocks of columns separated
+/// by columns that are blank on every line, including the operator line. The digits down each
+/// column of a block make one operand, and the block's operator can be under any of its columns.
+fn parse_columns(lines: &[String]) -> Result<Vec<Problem>> {
+    let width = lines
         .iter()
-        .map(|n| n.len())
+        .map(|l| l.chars().count())
         .max()
-        .expect("Couldn't find max");
-    for i in 0..max_length {
-        let op_line_char = operations_line.get(i);
-        let vertical_number: u64 = number_strings
-            .iter_mut()
-            .filter_map(|n| n.get(i).and_then(|d| d.to_digit(10)))
-            .fold(0, |accumulation, digit| accumulation * 10 + digit as u64);
-        if vertical_number == 0 {
+        .context("Worksheet is empty")?;
+    // Lines may have had trailing whitespace trimmed, so pad them back out
+    let grid: Vec<Vec<char>> = lines
+        .iter()
+        .map(|l| {
+            let mut row: Vec<char> = l.chars().collect();
+            row.resize(width, ' ');
+            row
+        })
+        .collect();
+    let (operations_row, number_rows) = grid.split_last().context("Worksheet is empty")?;
+    for (row, line) in number_rows.iter().enumerate() {
+        if let Some(col) = line.iter().position(|c| !(c.is_ascii_digit() || *c == ' ')) {
+            bail!("Unexpected {:?} on row {row} at column {col}", line[col]);
+        }
+    }
+    let is_blank = |col: usize| grid.iter().all(|row| row[col].is_whitespace());
+    let mut problems = Vec::new();
+    let mut col = 0;
+    while col < width {
+        if is_blank(col) {
+            col += 1;
             continue;
         }
-        if op_line_char == Some(&'*') || op_line_char == Some(&'+') {
-            sum += total;
-            total = vertical_number;
-            current_op = *op_line_char.unwrap();
-        } else {
-            match current_op {
-                '*' => total = total * vertical_number,
-                '+' => total = total + vertical_number,
-                _ => panic!("Unrecognised operand {op_line_char:?}"),
-            }
-        };
+        let start = col;
+        while col < width && !is_blank(col) {
+            col += 1;
+        }
+        problems.push(parse_block(operations_row, number_rows, start..col)?);
     }
-    sum += total;
-    sum
+    // Found left to right, but the worksheet is read right to left
+    problems.reverse();
+    Ok(problems)
+}
+
+/// The problem in one block of columns, with its operands in right to left order
+fn parse_block(
+    operations_row: &[char],
+    number_rows: &[Vec<char>],
+    columns: Range<usize>,
+) -> Result<Problem> {
+    let (first, last) = (columns.start, columns.end - 1);
+    let operations: String = operations_row[columns.clone()].iter().collect();
+    let mut tokens = operator_tokens(&operations);
+    let (offset, symbol) = tokens.next().with_context(|| {
+        format!("Columns {first}-{last} have no operator; are the rows aligned?")
+    })?;
+    if let Some((other, _)) = tokens.next() {
+        bail!(
+            "Columns {first}-{last} have operators at columns {} and {}; are the rows aligned?",
+            first + offset,
+            first + other
+        );
+    }
+    for (row, line) in number_rows.iter().enumerate() {
+        let numbers = line[columns.clone()]
+            .chunk_by(|a, b| a.is_ascii_digit() == b.is_ascii_digit())
+            .filter(|run| run[0].is_ascii_digit())
+            .count();
+        ensure!(
+            numbers <= 1,
+            "Row {row} has {numbers} numbers in columns {first}-{last}; are the rows aligned?"
+        );
+    }
+    let mut operands = Vec::new();
+    for col in columns.rev() {
+        let digits: Vec<u64> = number_rows
+            .iter()
+            .filter_map(|row| row[col].to_digit(10))
+            .map(u64::from)
+            .collect();
+        if digits.is_empty() {
+            continue;
+        }
+        let operand = digits
+            .iter()
+            .try_fold(0_u64, |number, &digit| {
+                number.checked_mul(10)?.checked_add(digit)
+            })
+            .with_context(|| format!("Number in column {col} is too big"))?;
+        operands.push(operand);
+    }
+    Ok(Problem {
+        operands,
+        op: Op::parse(symbol)?,
+        column: first + offset,
+    })
+}
+
+/// Each whitespace separated operator on the line, with the column it starts at
+fn operator_tokens(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    line.char_indices()
+        .filter(|&(i, c)| {
+            !c.is_whitespace() && (i == 0 || line[..i].ends_with(char::is_whitespace))
+        })
+        .map(|(i, _)| {
+            let end = line[i..]
+                .find(char::is_whitespace)
+                .map_or(line.len(), |len| i + len);
+            (i, &line[i..end])
+        })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rstest::rstest;
+
+    fn example() -> Vec<String> {
+        [
+            "123 328  51 64 ",
+            " 45 64  387 23 ",
+            "  6 98  215 314",
+            "*   +   *   +  ",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn test_parse_rows() {
+        let problems = parse_rows(&example()).unwrap();
+        assert_eq!(
+            problems[0],
+            Problem {
+                operands: vec![123, 45, 6],
+                op: Op::Multiply,
+                column: 0
+            }
+        );
+        assert_eq!(problems[3].to_string(), "64 + 23 + 314");
+        assert_eq!(problems[3].column, 12);
+        assert_eq!(problems[3].evaluate::<i128>().unwrap(), 401);
+    }
+
+    #[test]
+    fn test_parse_columns() {
+        let problems = parse_columns(&example()).unwrap();
+        let shown: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+        assert_eq!(
+            shown,
+            [
+                "4 + 431 + 623",
+                "175 * 581 * 32",
+                "8 + 248 + 369",
+                "356 * 24 * 1"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let mut lines = example();
+        lines[3] = "*   +   %   +  ".to_string();
+        assert!(parse_rows(&lines).is_err());
+        assert!(parse_columns(&lines).is_err());
+        let mut lines = example();
+        lines[1] = " 45 64  387".to_string();
+        assert!(parse_rows(&lines).is_err());
+    }
+
+    #[test]
+    fn test_column_segmentation() {
+        let lines: Vec<String> = ["10  7  ", "20  80 ", "  -   *"]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        let problems = parse_columns(&lines).unwrap();
+        let shown: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+        assert_eq!(shown, ["0 * 78", "0 - 12"]);
+        assert_eq!(problems[0].column, 6);
+        assert_eq!(problems[1].column, 2);
+        assert_eq!(solve_part_b(&lines), -12);
+    }
+
+    #[rstest]
+    #[case(&["12  3", "4 5 6", "+   *"], "Row 1 has 2 numbers in columns 0-2")]
+    #[case(&["12 3", "45 6", "   *"], "Columns 0-1 have no operator")]
+    #[case(&["123", "456", "+ *"], "Columns 0-2 have operators at columns 0 and 2")]
+    #[case(&["12 x", "+  *"], "Unexpected 'x' on row 0 at column 3")]
+    fn test_misaligned_columns(#[case] lines: &[&str], #[case] expected: &str) {
+        let lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        let err = parse_columns(&lines).unwrap_err().to_string();
+        assert!(err.starts_with(expected), "{err}");
+    }
+
+    fn problem(op: Op, operands: &[u64]) -> Problem {
+        Problem {
+            operands: operands.to_vec(),
+            op,
+            column: 7,
+        }
+    }
+
+    #[rstest]
+    #[case(Op::Subtract, &[10, 3, 20], -13)]
+    #[case(Op::Divide, &[100, 7, 2], 7)]
+    #[case(Op::Power, &[2, 10, 2], 1_048_576)]
+    #[case(Op::Max, &[3, 9, 4], 9)]
+    #[case(Op::Min, &[3, 9, 4], 3)]
+    fn test_operators(#[case] op: Op, #[case] operands: &[u64], #[case] expected: i128) {
+        let problem = problem(op, operands);
+        assert_eq!(problem.evaluate::<i128>().unwrap(), expected);
+        assert_eq!(
+            problem.evaluate::<BigInt>().unwrap().to_string(),
+            expected.to_string()
+        );
+    }
+
+    #[test]
+    fn test_powers_agree() {
+        for base in [-3_i64, -2, -1, 0, 1, 2, 5] {
+            for exponent in [0, 1, 2, 3, 7, 40, u64::MAX - 1, u64::MAX] {
+                let small = (base as i128).apply(Op::Power, exponent);
+                let mut big = BigInt::from_u64(base.unsigned_abs());
+                if base < 0 {
+                    big = big.negate();
+                }
+                let big = big.apply(Op::Power, exponent);
+                match small {
+                    Ok(small) => assert_eq!(
+                        big.unwrap().to_string(),
+                        small.to_string(),
+                        "{base}^{exponent}"
+                    ),
+                    Err(_) => assert!(base.abs() > 1, "{base}^{exponent}"),
+                }
+            }
+        }
+        assert_eq!(problem(Op::Power, &[0, 0]).evaluate::<i128>().unwrap(), 1);
+        assert_eq!(problem(Op::Power, &[5, 0]).evaluate::<i128>().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_big_power_limit() {
+        let err = problem(Op::Power, &[2, 4_000_000_000])
+            .evaluate::<BigInt>()
+            .unwrap_err();
+        assert!(err.to_string().starts_with("Overflow"), "{err}");
+        assert!(
+            problem(Op::Power, &[3, 1_000_000])
+                .evaluate::<BigInt>()
+                .is_err()
+        );
+        // Just under the limit is still worked out
+        let power = problem(Op::Power, &[2, 100_000])
+            .evaluate::<BigInt>()
+            .unwrap();
+        assert_eq!(power.to_string().len(), 30_103);
+        assert_eq!(
+            problem(Op::Power, &[1, u64::MAX])
+                .evaluate::<BigInt>()
+                .unwrap()
+                .to_string(),
+            "1"
+        );
+    }
+
+    #[test]
+    fn test_arithmetic_errors() {
+        let too_big = problem(Op::Multiply, &[u64::MAX, u64::MAX, u64::MAX]);
+        let err = too_big.evaluate::<i128>().unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("Overflow in the problem at column 7")
+        );
+        let exact = "6277101735386680762814942322444851025767571854389858533375";
+        assert_eq!(too_big.evaluate::<BigInt>().unwrap().to_string(), exact);
+        let divide_by_zero = problem(Op::Divide, &[5, 0]);
+        assert!(
+            divide_by_zero
+                .evaluate::<BigInt>()
+                .unwrap_err()
+                .to_string()
+                .starts_with("Division by zero")
+        );
+    }
+
+    #[test]
+    fn test_big_int() {
+        let a = BigInt::from_u64(1_000_000_000_000);
+        let b = a.apply(Op::Subtract, u64::MAX).unwrap();
+        assert_eq!(b.to_string(), "-18446743073709551615");
+        assert_eq!(
+            b.apply(Op::Divide, 1000).unwrap().to_string(),
+            "-18446743073709551"
+        );
+        assert_eq!(
+            b.apply(Op::Power, 2).unwrap().to_string(),
+            (18446743073709551615_u128 * 18446743073709551615).to_string()
+        );
+        assert_eq!(Number::add(&b, &b.negate()).unwrap().to_string(), "0");
+        assert_eq!(b.apply(Op::Max, 0).unwrap().to_string(), "0");
+        assert_eq!(
+            BigInt::from_u64(2)
+                .apply(Op::Power, 200)
+                .unwrap()
+                .to_string()
+                .len(),
+            61
+        );
+    }
+
+    #[test]
+    fn test_operator_tokens() {
+        let tokens: Vec<_> = operator_tokens("max  +  min ^").collect();
+        assert_eq!(tokens, [(0, "max"), (5, "+"), (8, "min"), (12, "^")]);
+        let lines: Vec<String> = ["12 3 ", " 4 56", "max min"]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        assert_eq!(solve_part_a(&lines), 12 + 3);
+    }
 
     #[test]
     fn test_part_a() {
@@ -82,6 +752,14 @@ mod tests {
     #[test]
     fn test_part_b() {
         assert_eq!(3263827, solve_part_b(&load_input(DAY, Input::Test)));
-        // assert_eq!(6503327062445, solve_part_b(&load_input(DAY, Input::Puzzle)));
+    }
+
+    #[rstest]
+    #[case(&["10 4", "20 5", "*  +"], 45)]
+    #[case(&["101", "202", "+  "], 24)]
+    #[case(&["1 02", "3 04", "+ * "], 13)]
+    fn test_part_b_zero_columns(#[case] lines: &[&str], #[case] expected: i128) {
+        let lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        assert_eq!(solve_part_b(&lines), expected);
     }
 }
@@ -1,84 +1,1003 @@
+use anyhow::{Context, Result, bail, ensure};
 use aoc25::{Input, load_input};
+use plotters::coord::Shift;
+use plotters::prelude::*;
 use std::collections::{HashMap, HashSet};
+use std::path::Path;
 
 const DAY: u8 = 7;
+const CELL_PIXELS: u32 = 6;
 
-fn main() {
-    let lines = load_input(DAY, Input::Puzzle);
-    let a = solve_part_a(&lines);
-    println!("Solution to a: {a}");
-    let b = solve_part_b(&lines);
-    println!("Solution to b: {b}");
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
 }
 
-fn solve_part_a(lines: &[String]) -> u64 {
-    solve_for_beam_count_and_split_count(lines).1
+impl Direction {
+    /// (row, column) step
+    fn offset(&self) -> (isize, isize) {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::Down => (1, 0),
+            Direction::Left => (0, -1),
+            Direction::Right => (0, 1),
+        }
+    }
+
+    fn perpendicular(&self) -> [Direction; 2] {
+        match self {
+            Direction::Up | Direction::Down => [Direction::Left, Direction::Right],
+            Direction::Left | Direction::Right => [Direction::Up, Direction::Down],
+        }
+    }
+
+    fn reflect(&self, mirror: Mirror) -> Direction {
+        match (mirror, self) {
+            (Mirror::Slash, Direction::Down) | (Mirror::Backslash, Direction::Up) => {
+                Direction::Left
+            }
+            (Mirror::Slash, Direction::Up) | (Mirror::Backslash, Direction::Down) => {
+                Direction::Right
+            }
+            (Mirror::Slash, Direction::Right) | (Mirror::Backslash, Direction::Left) => {
+                Direction::Up
+            }
+            (Mirror::Slash, Direction::Left) | (Mirror::Backslash, Direction::Right) => {
+                Direction::Down
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Mirror {
+    Slash,
+    Backslash,
 }
 
-fn solve_part_b(lines: &[String]) -> u64 {
-    solve_for_beam_count_and_split_count(lines).0.values().sum()
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Component {
+    Empty,
+    Start,
+    /// Beams carry on from either side of it
+    Splitter,
+    /// Beams carry on from either side of it and straight through
+    ThreeWaySplitter,
+    Mirror(Mirror),
+    Absorber,
 }
 
-fn solve_for_beam_count_and_split_count(lines: &[String]) -> (HashMap<usize, u64>, u64) {
-    let mut beam_count_by_index = HashMap::new();
-    beam_count_by_index.insert(start_index(&lines[0]), 1);
-    let mut beam_split_count = 0;
-    for line in lines[1..].iter() {
-        let beam_props =
-            calculate_beam_splitting(&beam_count_by_index, find_splitter_indices(line));
-        beam_count_by_index = beam_props.0;
-        beam_split_count += beam_props.1;
+impl Component {
+    fn parse(c: char) -> Result<Component> {
+        Ok(match c {
+            '.' => Component::Empty,
+            'S' => Component::Start,
+            '^' => Component::Splitter,
+            'Y' => Component::ThreeWaySplitter,
+            '/' => Component::Mirror(Mirror::Slash),
+            '\\' => Component::Mirror(Mirror::Backslash),
+            '#' => Component::Absorber,
+            _ => bail!("Unexpected char {c}"),
+        })
+    }
+
+    fn symbol(&self) -> char {
+        match self {
+            Component::Empty => '.',
+            Component::Start => 'S',
+            Component::Splitter => '^',
+            Component::ThreeWaySplitter => 'Y',
+            Component::Mirror(Mirror::Slash) => '/',
+            Component::Mirror(Mirror::Backslash) => '\\',
+            Component::Absorber => '#',
+        }
+    }
+
+    fn is_splitter(&self) -> bool {
+        matches!(self, Component::Splitter | Component::ThreeWaySplitter)
     }
-    (beam_count_by_index, beam_split_count)
 }
 
-fn calculate_beam_splitting(
-    beam_count_by_index: &HashMap<usize, u64>,
-    splitter_indices: HashSet<usize>,
-) -> (HashMap<usize, u64>, u64) {
-    let mut new_beam_count_by_index = HashMap::new();
-    let mut beam_split_count = 0;
-    for (beam_idx, beam_count) in beam_count_by_index {
-        if splitter_indices.contains(&beam_idx) {
-            beam_split_count += 1;
-            add_or_insert(beam_idx - 1, *beam_count, &mut new_beam_count_by_index);
-            add_or_insert(beam_idx + 1, *beam_count, &mut new_beam_count_by_index);
-        } else {
-            add_or_insert(*beam_idx, *beam_count, &mut new_beam_count_by_index);
+/// A beam travelling in `direction` as it enters, or leaves, the cell at `row` and `column`
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+struct Beam {
+    row: usize,
+    column: usize,
+    direction: Direction,
+}
+
+struct Manifold {
+    components: Vec<Vec<Component>>,
+    width: usize,
+    start: Beam,
+}
+
+impl Manifold {
+    /// Rows may be ragged, and are padded out with empty cells. The beam starts at `S` going down.
+    fn parse(lines: &[String]) -> Result<Manifold> {
+        let width = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+        let components = lines
+            .iter()
+            .map(|line| {
+                let mut row = line
+                    .chars()
+                    .map(Component::parse)
+                    .collect::<Result<Vec<_>>>()?;
+                row.resize(width, Component::Empty);
+                Ok(row)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let (row, column) = components
+            .iter()
+            .enumerate()
+            .find_map(|(row, line)| {
+                let column = line.iter().position(|c| *c == Component::Start)?;
+                Some((row, column))
+            })
+            .context("Couldn't find start index")?;
+        Ok(Manifold {
+            components,
+            width,
+            start: Beam {
+                row,
+                column,
+                direction: Direction::Down,
+            },
+        })
+    }
+
+    /// The beams that leave a cell when a beam enters it. Split beams appear beside the
+    /// splitter and carry on in the same direction, unless the splitter is on the edge, in
+    /// which case that beam leaves the manifold sideways.
+    fn departures(&self, beam: Beam) -> Vec<Beam> {
+        let beside = |side: Direction| match self.step(beam.row, beam.column, side) {
+            Some((row, column)) => Beam {
+                row,
+                column,
+                direction: beam.direction,
+            },
+            None => Beam {
+                direction: side,
+                ..beam
+            },
+        };
+        match self.components[beam.row][beam.column] {
+            Component::Empty | Component::Start => vec![beam],
+            Component::Splitter => beam
+                .direction
+                .perpendicular()
+                .into_iter()
+                .map(beside)
+                .collect(),
+            Component::ThreeWaySplitter => {
+                let mut departures: Vec<Beam> = beam
+                    .direction
+                    .perpendicular()
+                    .into_iter()
+                    .map(beside)
+                    .collect();
+                departures.push(beam);
+                departures
+            }
+            Component::Mirror(mirror) => vec![Beam {
+                direction: beam.direction.reflect(mirror),
+                ..beam
+            }],
+            Component::Absorber => Vec::new(),
         }
     }
-    (new_beam_count_by_index, beam_split_count)
+
+    /// Where a beam leaving a cell goes next, unless it leaves the manifold
+    fn next_beam(&self, departure: &Beam) -> Option<Beam> {
+        let (row, column) = self.step(departure.row, departure.column, departure.direction)?;
+        Some(Beam {
+            row,
+            column,
+            direction: departure.direction,
+        })
+    }
+
+    fn has_mirrors(&self) -> bool {
+        self.components
+            .iter()
+            .flatten()
+            .any(|c| matches!(c, Component::Mirror(_)))
+    }
+
+    /// The cell next to this one in `direction`, unless that's off the edge
+    fn step(&self, row: usize, column: usize, direction: Direction) -> Option<(usize, usize)> {
+        let (d_row, d_column) = direction.offset();
+        let row = row
+            .checked_add_signed(d_row)
+            .filter(|&r| r < self.components.len())?;
+        let column = column
+            .checked_add_signed(d_column)
+            .filter(|&c| c < self.width)?;
+        Some((row, column))
+    }
+}
+
+/// How beams spread through the manifold, counted in timelines
+#[derive(Debug, Default, Clone)]
+struct Propagation {
+    /// Beams leaving the manifold, keyed by the edge cell they leave from
+    exits: HashMap<Beam, u128>,
+    /// Beams passing through each cell, by row then column
+    cell_beams: Vec<Vec<u128>>,
+    /// Beams hitting each cell, which for splitters is the beams they split
+    entering: Vec<Vec<u128>>,
+}
+
+impl Propagation {
+    fn counts(&self, manifold: &Manifold) -> Result<BeamCounts> {
+        let mut counts = BeamCounts {
+            bottom: vec![0; manifold.width],
+            splits: manifold
+                .components
+                .iter()
+                .flatten()
+                .zip(self.entering.iter().flatten())
+                .filter(|(component, beams)| component.is_splitter() && **beams > 0)
+                .count() as u64,
+            ..BeamCounts::default()
+        };
+        for (exit, &beams) in &self.exits {
+            let count = match exit.direction {
+                Direction::Down => &mut counts.bottom[exit.column],
+                Direction::Up => &mut counts.top,
+                Direction::Left => &mut counts.left,
+                Direction::Right => &mut counts.right,
+            };
+            *count = add_beams(*count, beams)?;
+        }
+        Ok(counts)
+    }
 }
 
-fn add_or_insert(index: usize, value: u64, map: &mut HashMap<usize, u64>) {
-    map.entry(index)
-        .and_modify(|existing| *existing += value)
-        .or_insert(value);
+/// Where the beams (timelines) left the manifold, and how many splitters they hit
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+struct BeamCounts {
+    /// Beams leaving through the bottom, by column
+    bottom: Vec<u128>,
+    top: u128,
+    /// Beams falling off the left side
+    left: u128,
+    /// Beams falling off the right side
+    right: u128,
+    splits: u64,
 }
 
-fn start_index(line: &str) -> usize {
-    line.find('S').expect("Couldn't find start index")
+impl BeamCounts {
+    fn timelines(&self) -> Result<u128> {
+        [self.top, self.left, self.right]
+            .into_iter()
+            .chain(self.bottom.iter().copied())
+            .try_fold(0, add_beams)
+    }
 }
 
-fn find_splitter_indices(line: &str) -> HashSet<usize> {
-    line.chars()
+/// Sweep the beams down the manifold a row at a time. Much quicker than following beams
+/// one cell at a time, but only possible when there are no mirrors to turn them.
+fn sweep_rows(manifold: &Manifold) -> Result<BeamCounts> {
+    ensure!(!manifold.has_mirrors(), "Can't sweep rows through mirrors");
+    let width = manifold.width;
+    let mut counts = BeamCounts::default();
+    let mut beams = vec![0; width];
+    beams[manifold.start.column] = 1;
+    for (row, components) in manifold
+        .components
+        .iter()
         .enumerate()
-        .filter_map(|(idx, c)| is_splitter(&c).then_some(idx))
-        .collect()
+        .skip(manifold.start.row + 1)
+    {
+        let mut next = vec![0; width];
+        for (column, &count) in beams.iter().enumerate().filter(|(_, count)| **count > 0) {
+            let overflowed = || format!("Too many beams to count at row {row}, column {column}");
+            let component = components[column];
+            if component.is_splitter() {
+                counts.splits += 1;
+                match column.checked_sub(1) {
+                    Some(left) => {
+                        next[left] = add_beams(next[left], count).with_context(overflowed)?
+                    }
+                    None => counts.left = add_beams(counts.left, count).with_context(overflowed)?,
+                }
+                match column + 1 {
+                    right if right < width => {
+                        next[right] = add_beams(next[right], count).with_context(overflowed)?
+                    }
+                    _ => counts.right = add_beams(counts.right, count).with_context(overflowed)?,
+                }
+            }
+            if matches!(
+                component,
+                Component::Empty | Component::Start | Component::ThreeWaySplitter
+            ) {
+                next[column] = add_beams(next[column], count).with_context(overflowed)?;
+            }
+        }
+        beams = next;
+    }
+    counts.bottom = beams;
+    Ok(counts)
+}
+
+fn add_beams(total: u128, beams: u128) -> Result<u128> {
+    total.checked_add(beams).context("Too many beams to count")
+}
+
+/// Every way a beam can enter a cell, and the beams that leave it
+struct BeamGraph {
+    /// Each beam comes after all the beams that lead into it
+    order: Vec<Beam>,
+    departures: HashMap<Beam, Vec<Beam>>,
+}
+
+/// Follow every beam from the start until it leaves the manifold or is absorbed. Beams
+/// travelling the same way through the same cell are merged, so there's one node for each
+/// (cell, direction). The nodes are put in an order where every beam into a cell comes before
+/// it moves on, which only exists if beams can't go round in a loop.
+fn beam_graph(manifold: &Manifold) -> Result<BeamGraph> {
+    let mut departures: HashMap<Beam, Vec<Beam>> = HashMap::new();
+    let mut incoming: HashMap<Beam, usize> = HashMap::from([(manifold.start, 0)]);
+    let mut to_visit = vec![manifold.start];
+    while let Some(beam) = to_visit.pop() {
+        if departures.contains_key(&beam) {
+            continue;
+        }
+        let leaving = manifold.departures(beam);
+        for next in leaving
+            .iter()
+            .filter_map(|departure| manifold.next_beam(departure))
+        {
+            *incoming.entry(next).or_insert(0) += 1;
+            to_visit.push(next);
+        }
+        departures.insert(beam, leaving);
+    }
+
+    if incoming[&manifold.start] > 0 {
+        bail!(
+            "Beams go round in a loop through the start at row {}, column {}",
+            manifold.start.row,
+            manifold.start.column
+        );
+    }
+    let mut order = Vec::with_capacity(departures.len());
+    let mut ready = vec![manifold.start];
+    while let Some(beam) = ready.pop() {
+        order.push(beam);
+        for next in departures[&beam]
+            .iter()
+            .filter_map(|departure| manifold.next_beam(departure))
+        {
+            let remaining = incoming.get_mut(&next).expect("Every beam was followed");
+            *remaining -= 1;
+            if *remaining == 0 {
+                ready.push(next);
+            }
+        }
+    }
+    if order.len() < departures.len() {
+        let looping = incoming
+            .iter()
+            .filter(|(_, remaining)| **remaining > 0)
+            .map(|(beam, _)| (beam.row, beam.column))
+            .min()
+            .expect("Some beam wasn't visited");
+        bail!(
+            "Beams go round in a loop through row {}, column {}",
+            looping.0,
+            looping.1
+        );
+    }
+    Ok(BeamGraph { order, departures })
+}
+
+/// Count the beams through every cell and out of the manifold
+fn calculate_beam_splitting(manifold: &Manifold) -> Result<Propagation> {
+    let graph = beam_graph(manifold)?;
+    let height = manifold.components.len();
+    let mut propagation = Propagation {
+        exits: HashMap::new(),
+        cell_beams: vec![vec![0; manifold.width]; height],
+        entering: vec![vec![0; manifold.width]; height],
+    };
+    let mut beams = HashMap::from([(manifold.start, 1)]);
+    for beam in &graph.order {
+        let count = beams[beam];
+        let overflowed = || {
+            format!(
+                "Too many beams to count at row {}, column {}",
+                beam.row, beam.column
+            )
+        };
+        let entering = &mut propagation.entering[beam.row][beam.column];
+        *entering = add_beams(*entering, count).with_context(overflowed)?;
+        let passing = &mut propagation.cell_beams[beam.row][beam.column];
+        *passing = add_beams(*passing, count).with_context(overflowed)?;
+        for departure in &graph.departures[beam] {
+            if (departure.row, departure.column) != (beam.row, beam.column) {
+                let passing = &mut propagation.cell_beams[departure.row][departure.column];
+                *passing = add_beams(*passing, count).with_context(overflowed)?;
+            }
+            match manifold.next_beam(departure) {
+                Some(next) => add_or_insert(next, count, &mut beams).with_context(overflowed)?,
+                None => add_or_insert(*departure, count, &mut propagation.exits)
+                    .with_context(overflowed)?,
+            }
+        }
+    }
+    Ok(propagation)
+}
+
+/// How likely a splitter is to send a beam each way. The first side is the left of a beam
+/// going up or down, and above a beam going sideways.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct SplitWeights {
+    first_side: f64,
+    straight: f64,
+    second_side: f64,
+}
+
+impl SplitWeights {
+    fn default_for(component: Component) -> Option<SplitWeights> {
+        match component {
+            Component::Splitter => Some(SplitWeights {
+                first_side: 0.5,
+                straight: 0.0,
+                second_side: 0.5,
+            }),
+            Component::ThreeWaySplitter => Some(SplitWeights {
+                first_side: 1.0 / 3.0,
+                straight: 1.0 / 3.0,
+                second_side: 1.0 / 3.0,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Parse splitter annotations, one per line as `ROW,COLUMN FIRST_SIDE [STRAIGHT]`, where the
+/// second side gets whatever probability is left. Splitters without an annotation split
+/// evenly. Blank lines and lines starting with `#` are ignored.
+fn parse_split_weights(
+    manifold: &Manifold,
+    annotations: &str,
+) -> Result<HashMap<(usize, usize), SplitWeights>> {
+    let mut weights = HashMap::new();
+    for (line_num, line) in annotations.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let context = || {
+            format!(
+                "Couldn't parse annotation {line:?} on line {}",
+                line_num + 1
+            )
+        };
+        let mut fields = line.split_whitespace();
+        let (row, column) = fields
+            .next()
+            .and_then(|cell| cell.split_once(','))
+            .with_context(context)?;
+        let (row, column): (usize, usize) = (
+            row.parse().with_context(context)?,
+            column.parse().with_context(context)?,
+        );
+        let probabilities = fields
+            .map(|p| p.parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(context)?;
+        let component = manifold
+            .components
+            .get(row)
+            .and_then(|line| line.get(column))
+            .copied();
+        let (first_side, straight) = match (component, probabilities.as_slice()) {
+            (Some(Component::Splitter), &[first_side]) => (first_side, 0.0),
+            (Some(Component::ThreeWaySplitter), &[first_side]) => (first_side, 0.0),
+            (Some(Component::ThreeWaySplitter), &[first_side, straight]) => (first_side, straight),
+            (Some(component), _) if component.is_splitter() => {
+                bail!("Wrong number of probabilities for the splitter at {row},{column}")
+            }
+            _ => bail!("No splitter at {row},{column} to annotate"),
+        };
+        ensure!(
+            first_side >= 0.0 && straight >= 0.0 && first_side + straight <= 1.0,
+            "Probabilities for {row},{column} must be at least 0 and add up to no more than 1"
+        );
+        let split = SplitWeights {
+            first_side,
+            straight,
+            second_side: 1.0 - first_side - straight,
+        };
+        weights.insert((row, column), split);
+    }
+    Ok(weights)
+}
+
+/// The probability that the beam leaves the manifold from each edge cell, when each splitter
+/// sends it one way at random. Anything missing was absorbed. Probabilities are floats, so
+/// they're approximate: any split that isn't a power of 2, like the default three-way split,
+/// is rounded at every splitter, and the exits needn't add up to exactly 1 even when nothing's
+/// absorbed.
+fn exit_distribution(
+    manifold: &Manifold,
+    weights: &HashMap<(usize, usize), SplitWeights>,
+) -> Result<HashMap<Beam, f64>> {
+    let graph = beam_graph(manifold)?;
+    let mut probabilities = HashMap::from([(manifold.start, 1.0)]);
+    let mut exits = HashMap::new();
+    for beam in &graph.order {
+        let probability = probabilities.get(beam).copied().unwrap_or(0.0);
+        let component = manifold.components[beam.row][beam.column];
+        let split = weights
+            .get(&(beam.row, beam.column))
+            .copied()
+            .or_else(|| SplitWeights::default_for(component));
+        // Departures from splitters are the first side, the second side, then straight on
+        let departure_weights = match split {
+            Some(split) => vec![split.first_side, split.second_side, split.straight],
+            None => vec![1.0],
+        };
+        for (departure, weight) in graph.departures[beam].iter().zip(departure_weights) {
+            if probability * weight == 0.0 {
+                continue;
+            }
+            let target = match manifold.next_beam(departure) {
+                Some(next) => probabilities.entry(next),
+                None => exits.entry(*departure),
+            };
+            *target.or_insert(0.0) += probability * weight;
+        }
+    }
+    Ok(exits)
+}
+
+/// A splitter that at least one beam reached, and how many beams (timelines) reached it
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct SplitterHit {
+    row: usize,
+    column: usize,
+    beams: u128,
+}
+
+/// Everything that happened on the way through the manifold
+#[derive(Debug, Default, Clone)]
+struct BeamTrace {
+    components: Vec<Vec<Component>>,
+    hits: Vec<SplitterHit>,
+    /// The beams passing through each cell, by row then column
+    beams_by_row: Vec<Vec<u128>>,
+    /// Splitters no beam reached, as (row, column)
+    unreached: HashSet<(usize, usize)>,
+}
+
+impl BeamTrace {
+    fn new(manifold: &Manifold, propagation: &Propagation) -> BeamTrace {
+        let mut trace = BeamTrace {
+            components: manifold.components.clone(),
+            beams_by_row: propagation.cell_beams.clone(),
+            ..BeamTrace::default()
+        };
+        for (row, line) in manifold.components.iter().enumerate() {
+            for (column, component) in line.iter().enumerate() {
+                if !component.is_splitter() {
+                    continue;
+                }
+                match propagation.entering[row][column] {
+                    0 => {
+                        trace.unreached.insert((row, column));
+                    }
+                    beams => trace.hits.push(SplitterHit { row, column, beams }),
+                }
+            }
+        }
+        trace
+    }
+
+    fn max_beams(&self) -> u128 {
+        self.beams_by_row
+            .iter()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// How bright a cell with this many beams is, from 0 to 1, when the busiest cell has
+    /// `max_beams`. Beam counts grow exponentially down the manifold, so the scale is
+    /// logarithmic.
+    fn intensity(beams: u128, max_beams: u128) -> f64 {
+        if beams == 0 {
+            return 0.0;
+        }
+        let max = max_beams as f64;
+        if max <= 1.0 {
+            return 1.0;
+        }
+        (beams as f64).ln_1p() / max.ln_1p()
+    }
+
+    fn is_unreached(&self, row: usize, column: usize) -> bool {
+        self.unreached.contains(&(row, column))
+    }
+
+    /// Unreached splitters are `x`, other components are their own symbol, and beams are
+    /// shaded by intensity
+    fn to_ascii(&self) -> String {
+        const SHADES: &[u8] = b".:-=o0%&@";
+        let max_beams = self.max_beams();
+        self.components
+            .iter()
+            .zip(&self.beams_by_row)
+            .enumerate()
+            .map(|(row, (components, beams))| {
+                components
+                    .iter()
+                    .zip(beams)
+                    .enumerate()
+                    .map(|(column, (component, &beams))| match component {
+                        _ if self.is_unreached(row, column) => 'x',
+                        Component::Empty => {
+                            let shade =
+                                BeamTrace::intensity(beams, max_beams) * (SHADES.len() - 1) as f64;
+                            SHADES[shade.ceil() as usize] as char
+                        }
+                        _ => component.symbol(),
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn write_heatmap(&self, path: &Path) -> Result<()> {
+        let size = (
+            self.components.first().map_or(0, |row| row.len()) as u32 * CELL_PIXELS,
+            self.beams_by_row.len() as u32 * CELL_PIXELS,
+        );
+        let root = BitMapBackend::new(path, size).into_drawing_area();
+        self.draw_heatmap(&root)?;
+        root.present()?;
+        Ok(())
+    }
+
+    /// Beams go from dark blue to yellow as they get more intense. Components are white,
+    /// except absorbers which are grey and unreached splitters which are red.
+    fn draw_heatmap<DB: DrawingBackend>(
+        &self,
+        area: &DrawingArea<DB, Shift>,
+    ) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+        area.fill(&BLACK)?;
+        let max_beams = self.max_beams();
+        for (row, (components, beams)) in self.components.iter().zip(&self.beams_by_row).enumerate()
+        {
+            for (column, (component, &beams)) in components.iter().zip(beams).enumerate() {
+                let colour = match component {
+                    _ if self.is_unreached(row, column) => RED.to_rgba(),
+                    Component::Empty if beams > 0 => {
+                        let intensity = BeamTrace::intensity(beams, max_beams);
+                        HSLColor(0.66 - 0.5 * intensity, 0.9, 0.25 + 0.3 * intensity).to_rgba()
+                    }
+                    Component::Empty => continue,
+                    Component::Absorber => RGBColor(96, 96, 96).to_rgba(),
+                    _ => WHITE.to_rgba(),
+                };
+                let top_left = (
+                    column as i32 * CELL_PIXELS as i32,
+                    row as i32 * CELL_PIXELS as i32,
+                );
+                let bottom_right = (
+                    top_left.0 + CELL_PIXELS as i32 - 1,
+                    top_left.1 + CELL_PIXELS as i32 - 1,
+                );
+                area.draw(&Rectangle::new([top_left, bottom_right], colour.filled()))?;
+            }
+        }
+        Ok(())
+    }
 }
 
-fn is_splitter(c: &char) -> bool {
-    match c {
-        '^' => true,
-        '.' => false,
-        _ => panic!("Unexpected char {c}"),
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let arg_value = |flag: &str| args.iter().skip_while(|arg| *arg != flag).nth(1);
+    let lines = load_input(DAY, Input::Puzzle);
+    let a = solve_part_a(&lines);
+    println!("Solution to a: {a}");
+    let b = solve_part_b(&lines);
+    println!("Solution to b: {b}");
+
+    let heatmap = arg_value("--heatmap");
+    let show_ascii = args.iter().any(|arg| arg == "--ascii");
+    if args.iter().any(|arg| arg == "--trace") || show_ascii || heatmap.is_some() {
+        let mut trace = BeamTrace::default();
+        let counts = solve_for_beam_count_and_split_count(&lines, Some(&mut trace));
+        println!("Splitters hit:");
+        for hit in &trace.hits {
+            println!(
+                "  row {}, column {}: {} beams",
+                hit.row, hit.column, hit.beams
+            );
+        }
+        let mut unreached: Vec<_> = trace.unreached.iter().collect();
+        unreached.sort();
+        println!("Splitters never reached: {unreached:?}");
+        println!(
+            "Beams falling off the left: {}, off the right: {}, out of the top: {}",
+            counts.left, counts.right, counts.top
+        );
+        if show_ascii {
+            println!("{}", trace.to_ascii());
+        }
+        if let Some(path) = heatmap {
+            trace
+                .write_heatmap(Path::new(path))
+                .expect("Couldn't write heatmap");
+        }
+    }
+    if let Some(path) = arg_value("--probabilities") {
+        let manifold = Manifold::parse(&lines).expect("Couldn't parse manifold");
+        let annotations = std::fs::read_to_string(path).expect("Couldn't read annotations");
+        let weights =
+            parse_split_weights(&manifold, &annotations).expect("Couldn't parse annotations");
+        let distribution = exit_distribution(&manifold, &weights).expect("Couldn't follow beams");
+        let mut exits: Vec<_> = distribution.iter().collect();
+        exits.sort_by_key(|(exit, _)| (exit.row, exit.column));
+        for (exit, probability) in exits {
+            println!(
+                "Leaves going {:?} from row {}, column {}: {probability}",
+                exit.direction, exit.row, exit.column
+            );
+        }
+        let (bottom_weight, bottom_total) = distribution
+            .iter()
+            .filter(|(exit, _)| exit.direction == Direction::Down)
+            .fold((0.0, 0.0), |(weight, total), (exit, probability)| {
+                (
+                    weight + probability,
+                    total + probability * exit.column as f64,
+                )
+            });
+        // Rounding can leave the total a little over 1
+        let absorbed = (1.0 - distribution.values().sum::<f64>()).max(0.0);
+        println!("Absorbed: {absorbed}");
+        if bottom_weight > 0.0 {
+            println!(
+                "Expected column out of the bottom: {}",
+                bottom_total / bottom_weight
+            );
+        }
     }
 }
 
+fn solve_part_a(lines: &[String]) -> u64 {
+    solve_for_beam_count_and_split_count(lines, None).splits
+}
+
+fn solve_part_b(lines: &[String]) -> u128 {
+    solve_for_beam_count_and_split_count(lines, None)
+        .timelines()
+        .expect("Couldn't count timelines")
+}
+
+/// Where the beams leave the manifold and how many splitters they hit. Rows are swept when
+/// they can be, otherwise beams are followed cell by cell, as they are when the whole
+/// propagation is recorded in `trace`.
+fn solve_for_beam_count_and_split_count(
+    lines: &[String],
+    trace: Option<&mut BeamTrace>,
+) -> BeamCounts {
+    let manifold = Manifold::parse(lines).expect("Couldn't parse manifold");
+    if trace.is_none() && !manifold.has_mirrors() {
+        return sweep_rows(&manifold).expect("Couldn't sweep beams");
+    }
+    let propagation = calculate_beam_splitting(&manifold).expect("Couldn't follow beams");
+    if let Some(trace) = trace {
+        *trace = BeamTrace::new(&manifold, &propagation);
+    }
+    propagation.counts(&manifold).expect("Couldn't count beams")
+}
+
+fn add_or_insert(index: Beam, value: u128, map: &mut HashMap<Beam, u128>) -> Result<()> {
+    let existing = map.entry(index).or_insert(0);
+    *existing = add_beams(*existing, value)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_trace() {
+        let mut trace = BeamTrace::default();
+        let lines = load_input(DAY, Input::Test);
+        let counts = solve_for_beam_count_and_split_count(&lines, Some(&mut trace));
+        assert_eq!(counts, solve_for_beam_count_and_split_count(&lines, None));
+        assert_eq!(trace.hits.len() as u64, counts.splits);
+        assert_eq!(
+            trace.hits[0],
+            SplitterHit {
+                row: 2,
+                column: 7,
+                beams: 1
+            }
+        );
+        assert_eq!(trace.unreached, HashSet::from([(14, 9)]));
+        assert_eq!(trace.beams_by_row.len(), lines.len());
+        assert_eq!(trace.beams_by_row.last(), Some(&counts.bottom));
+        let ascii = trace.to_ascii();
+        let ascii: Vec<&str> = ascii.lines().collect();
+        assert_eq!(ascii[0], ".......S.......");
+        assert_eq!(ascii[2], "......=^=......");
+        assert_eq!(ascii[14], "=^o^@^@^@xo==^=");
+        assert_eq!(ascii[12], ".=^=%%^&%.o=^=.");
+    }
+
+    fn parse_manifold(rows: &[&str]) -> Manifold {
+        let lines: Vec<String> = rows.iter().map(|row| row.to_string()).collect();
+        Manifold::parse(&lines).unwrap()
+    }
+
+    #[test]
+    fn test_mirrors_send_beams_up() {
+        let manifold = parse_manifold(&[".....", ".S...", r".\./."]);
+        let propagation = calculate_beam_splitting(&manifold).unwrap();
+        let exit = Beam {
+            row: 0,
+            column: 3,
+            direction: Direction::Up,
+        };
+        assert_eq!(propagation.exits, HashMap::from([(exit, 1)]));
+        assert_eq!(propagation.cell_beams[2], [0, 1, 1, 1, 0]);
+    }
+
+    #[test]
+    fn test_three_way_splitter_and_absorber() {
+        let manifold = parse_manifold(&["..S..", "..Y..", ".#..."]);
+        let propagation = calculate_beam_splitting(&manifold).unwrap();
+        assert_eq!(propagation.exits.values().sum::<u128>(), 2);
+        assert_eq!(propagation.cell_beams[1], [0, 1, 1, 1, 0]);
+        assert_eq!(propagation.cell_beams[2], [0, 1, 1, 1, 0]);
+    }
+
+    #[test]
+    fn test_beams_off_the_sides() {
+        let manifold = parse_manifold(&["..S", "..^", ".Y.", "^.."]);
+        let expected = BeamCounts {
+            bottom: vec![0, 2, 1],
+            top: 0,
+            left: 1,
+            right: 1,
+            splits: 3,
+        };
+        assert_eq!(sweep_rows(&manifold).unwrap(), expected);
+        let propagation = calculate_beam_splitting(&manifold).unwrap();
+        assert_eq!(propagation.counts(&manifold).unwrap(), expected);
+        assert_eq!(expected.timelines().unwrap(), 5);
+    }
+
+    #[test]
+    fn test_overflow() {
+        // The beams in the middle double every two rows
+        let mut rows = vec![".S."];
+        for _ in 0..130 {
+            rows.extend([".^.", "^.^"]);
+        }
+        let manifold = parse_manifold(&rows);
+        let err = sweep_rows(&manifold).unwrap_err();
+        assert!(
+            err.to_string().starts_with("Too many beams to count"),
+            "{err}"
+        );
+        assert!(calculate_beam_splitting(&manifold).is_err());
+        assert!(sweep_rows(&parse_manifold(&rows[..250])).is_ok());
+    }
+
+    #[test]
+    fn test_exit_total_overflow() {
+        // Two exits off the left that each fit, but not together
+        let manifold = parse_manifold(&[".S.", "...", "..."]);
+        let left = |row| Beam {
+            row,
+            column: 0,
+            direction: Direction::Left,
+        };
+        let propagation = Propagation {
+            exits: HashMap::from([(left(1), 1 << 127), (left(2), 1 << 127)]),
+            ..Propagation::default()
+        };
+        let err = propagation.counts(&manifold).unwrap_err();
+        assert!(
+            err.to_string().starts_with("Too many beams to count"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_exit_distribution() {
+        let manifold = parse_manifold(&["..S..", "..^..", ".Y...", "..#.."]);
+        let even = exit_distribution(&manifold, &HashMap::new()).unwrap();
+        let bottom = |column| Beam {
+            row: 3,
+            column,
+            direction: Direction::Down,
+        };
+        assert_eq!(even.len(), 3);
+        assert_close(even[&bottom(0)], 0.5 / 3.0);
+        assert_close(even[&bottom(1)], 0.5 / 3.0);
+        assert_close(even[&bottom(3)], 0.5);
+
+        let annotations = "# row,column first_side straight\n1,2 0.25\n\n2,1 0 0.5\n";
+        let weights = parse_split_weights(&manifold, annotations).unwrap();
+        let weighted = exit_distribution(&manifold, &weights).unwrap();
+        assert_eq!(weighted.len(), 2);
+        assert_close(weighted[&bottom(1)], 0.125);
+        // The rest of the beam that went left was absorbed
+        assert_close(weighted[&bottom(3)], 0.75);
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-12,
+            "{actual} isn't close to {expected}"
+        );
+    }
+
+    #[test]
+    fn test_exit_distribution_adds_up() {
+        // Three-way splits that can't be represented exactly, and nowhere to be absorbed
+        let width = 41;
+        let mut rows = vec![format!("{}S{}", ".".repeat(20), ".".repeat(20))];
+        for row in 1..20 {
+            rows.push(
+                (0..width)
+                    .map(|column| if (row + column) % 2 == 1 { 'Y' } else { '.' })
+                    .collect(),
+            );
+        }
+        let rows: Vec<&str> = rows.iter().map(|row| row.as_str()).collect();
+        let distribution = exit_distribution(&parse_manifold(&rows), &HashMap::new()).unwrap();
+        assert!(distribution.len() > 1);
+        assert_close(distribution.values().sum(), 1.0);
+    }
+
+    #[test]
+    fn test_bad_annotations() {
+        let manifold = parse_manifold(&["..S..", "..^..", ".Y..."]);
+        for annotations in [
+            "0,2 0.5",
+            "1,2 0.5 0.5",
+            "2,1 0.75 0.5",
+            "1,2 half",
+            "1 0.5",
+        ] {
+            assert!(
+                parse_split_weights(&manifold, annotations).is_err(),
+                "{annotations}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_beam_loop() {
+        let manifold = parse_manifold(&[r"./S\.", "..^..", r".\./."]);
+        let err = calculate_beam_splitting(&manifold).unwrap_err();
+        assert!(
+            err.to_string().starts_with("Beams go round in a loop"),
+            "{err}"
+        );
+        let manifold = parse_manifold(&[r"/\.", ".S.", r"\/."]);
+        let err = calculate_beam_splitting(&manifold).unwrap_err();
+        assert!(err.to_string().contains("through the start"), "{err}");
+    }
+
     #[test]
     fn test_part_a() {
         assert_eq!(21, solve_part_a(&load_input(DAY, Input::Test)));
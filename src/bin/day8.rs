@@ -1,8 +1,13 @@
+use anyhow::{Result, bail};
 use aoc25::{Input, load_input};
-use itertools::Itertools;
-use std::collections::HashSet;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
 use std::fmt;
 use std::fmt::Formatter;
+use std::ops::Range;
+use std::path::Path;
 
 const DAY: u8 = 8;
 
@@ -13,109 +18,778 @@ struct Point3d {
     z: i64,
 }
 
+impl Point3d {
+    fn coordinate(&self, axis: usize) -> i64 {
+        match axis {
+            0 => self.x,
+            1 => self.y,
+            _ => self.z,
+        }
+    }
+}
+
 impl fmt::Debug for Point3d {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "({},{},{})", self.x, self.y, self.z)
     }
 }
 
-#[derive(PartialEq, Hash, Clone, Copy)]
-struct PointPair {
-    a: Point3d,
-    b: Point3d,
+/// How far apart two points are. Every metric is worked out exactly in integers, and pairs
+/// the same distance apart are ordered by their lower point index and then their higher one,
+/// so pairs come out in the same order on every platform.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+enum Metric {
+    /// Orders pairs the same as Euclidean distance, without the rounding of a square root
+    #[default]
+    SquaredEuclidean,
+    Manhattan,
+    Chebyshev,
 }
 
-impl fmt::Debug for PointPair {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "a: {:?}, b: {:?}", self.a, self.b)
+impl Metric {
+    fn parse(name: &str) -> Result<Metric> {
+        match name {
+            "euclidean" => Ok(Metric::SquaredEuclidean),
+            "manhattan" => Ok(Metric::Manhattan),
+            "chebyshev" => Ok(Metric::Chebyshev),
+            _ => bail!("Unknown metric {name:?}, expected euclidean, manhattan or chebyshev"),
+        }
+    }
+
+    fn distance(&self, a: &Point3d, b: &Point3d) -> i128 {
+        let diffs =
+            (0..3).map(|axis| (a.coordinate(axis) as i128 - b.coordinate(axis) as i128).abs());
+        match self {
+            Metric::SquaredEuclidean => diffs.map(|d| d * d).sum(),
+            Metric::Manhattan => diffs.sum(),
+            Metric::Chebyshev => diffs.max().unwrap_or(0),
+        }
+    }
+
+    /// The least distance to a point `diff` away along one axis
+    fn axis_bound(&self, diff: i128) -> i128 {
+        match self {
+            Metric::SquaredEuclidean => diff * diff,
+            Metric::Manhattan | Metric::Chebyshev => diff.abs(),
+        }
+    }
+
+    /// The distance as a length, for dendrogram heights and exports
+    fn length(&self, distance: i128) -> f64 {
+        match self {
+            Metric::SquaredEuclidean => (distance as f64).sqrt(),
+            Metric::Manhattan | Metric::Chebyshev => distance as f64,
+        }
     }
 }
 
-type Circuit = HashSet<Point3d>;
+/// A connection between the points at indices `a` and `b`, where `a < b`
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+struct Connection {
+    a: usize,
+    b: usize,
+    /// Measured with `metric`
+    distance: i128,
+    metric: Metric,
+}
 
-fn main() {
-    let lines = load_input(DAY, Input::Puzzle);
-    let a = solve_part_a(&lines, 1000);
-    println!("Solution to a: {a}");
-    let b = solve_part_b(&lines);
-    println!("Solution to b: {b}");
+impl Connection {
+    fn length(&self) -> f64 {
+        self.metric.length(self.distance)
+    }
 }
 
-fn solve_part_a(lines: &[String], num_connection_to_make: u32) -> usize {
-    let mut all_pairs_iter = point_pairs_sorted_by_distance(lines).into_iter();
-    let mut circuits: Vec<Circuit> = Vec::new();
+/// Which circuit each point is in, as a disjoint-set forest with union by size
+struct Circuits {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    count: usize,
+}
 
-    for _ in 0..num_connection_to_make {
-        let next_connection = all_pairs_iter
-            .next()
-            .expect("No more connections available");
-        circuits = resolve_circuits_with_new_connection(circuits, &next_connection);
+impl Circuits {
+    /// Every point starts in a circuit of its own
+    fn new(num_points: usize) -> Circuits {
+        Circuits {
+            parent: (0..num_points).collect(),
+            size: vec![1; num_points],
+            count: num_points,
+        }
+    }
+
+    /// The point representing the circuit this point is in. Every point on the way is
+    /// pointed straight at it, so later lookups are quicker.
+    fn find(&mut self, point: usize) -> usize {
+        let mut root = point;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        let mut current = point;
+        while self.parent[current] != root {
+            current = std::mem::replace(&mut self.parent[current], root);
+        }
+        root
+    }
+
+    /// Join the circuits the two points are in. False if they were already the same circuit.
+    fn connect(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        let (big, small) = if self.size[a] >= self.size[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parent[small] = big;
+        self.size[big] += self.size[small];
+        self.count -= 1;
+        true
+    }
+
+    /// The circuit each point is in, numbered in order of the lowest point in them
+    fn labels(&mut self) -> Vec<usize> {
+        let mut labels: HashMap<usize, usize> = HashMap::new();
+        (0..self.parent.len())
+            .map(|point| {
+                let next_label = labels.len();
+                *labels.entry(self.find(point)).or_insert(next_label)
+            })
+            .collect()
+    }
+
+    fn sizes(&self) -> Vec<usize> {
+        (0..self.parent.len())
+            .filter(|&point| self.parent[point] == point)
+            .map(|root| self.size[root])
+            .collect()
     }
-    let mut sizes: Vec<usize> = circuits.iter().map(|c| c.len()).sorted().collect();
-    sizes.pop().unwrap_or(1) * sizes.pop().unwrap_or(1) * sizes.pop().unwrap_or(1)
 }
 
-fn solve_part_b(lines: &[String]) -> i64 {
-    let total_points = lines.len();
-    let mut all_pairs_iter = point_pairs_sorted_by_distance(lines).into_iter();
-    let mut circuits: Vec<Circuit> = Vec::new();
-    let mut final_connection = None;
-    while circuits.first().is_none_or(|c| c.len() < total_points) {
-        let next_connection = all_pairs_iter
-            .next()
-            .expect("No more connections available");
-        circuits = resolve_circuits_with_new_connection(circuits, &next_connection);
-        final_connection = Some(next_connection)
-    }
-    let connection = final_connection.expect("Didn't make any connnections");
-    connection.a.x * connection.b.x
-}
-
-fn resolve_circuits_with_new_connection(
-    circuits: Vec<Circuit>,
-    next_connection: &PointPair,
-) -> Vec<Circuit> {
-    let mut new_circ = HashSet::from([next_connection.a, next_connection.b]);
-    let mut next_circuits: Vec<Circuit> = Vec::new();
-    for circuit in circuits {
-        if either_point_in_circuit(&next_connection, &circuit) {
-            new_circ.extend(circuit);
+/// A k-d tree over the points, stored as the point indices in an order where the middle of
+/// each range splits the rest of it on one axis
+struct KdTree<'a> {
+    points: &'a [Point3d],
+    order: Vec<usize>,
+    metric: Metric,
+}
+
+impl KdTree<'_> {
+    fn new(points: &[Point3d], metric: Metric) -> KdTree<'_> {
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        build_kd_tree(points, &mut order, 0);
+        KdTree {
+            points,
+            order,
+            metric,
+        }
+    }
+
+    /// The `k` points nearest to the point at `target`, not counting itself, as
+    /// (distance, index) pairs. Ties are broken by index, so these are always the
+    /// first `k` of the `k + 1` nearest.
+    fn nearest(&self, target: usize, k: usize) -> Vec<(i128, usize)> {
+        let mut best = BinaryHeap::with_capacity(k + 1);
+        self.search(0..self.order.len(), 0, target, k, &mut best);
+        best.into_sorted_vec()
+    }
+
+    fn search(
+        &self,
+        range: Range<usize>,
+        depth: usize,
+        target: usize,
+        k: usize,
+        best: &mut BinaryHeap<(i128, usize)>,
+    ) {
+        if range.is_empty() || k == 0 {
+            return;
+        }
+        let mid = range.start + range.len() / 2;
+        let index = self.order[mid];
+        if index != target {
+            let candidate = (
+                self.metric
+                    .distance(&self.points[target], &self.points[index]),
+                index,
+            );
+            if best.len() < k {
+                best.push(candidate);
+            } else if best.peek().is_some_and(|worst| candidate < *worst) {
+                best.pop();
+                best.push(candidate);
+            }
+        }
+        let axis = depth % 3;
+        let diff = self.points[target].coordinate(axis) as i128
+            - self.points[index].coordinate(axis) as i128;
+        let (near, far) = if diff < 0 {
+            (range.start..mid, mid + 1..range.end)
         } else {
-            next_circuits.push(circuit)
+            (mid + 1..range.end, range.start..mid)
+        };
+        self.search(near, depth + 1, target, k, best);
+        // Points on the far side are at least `diff` away, and could still tie
+        let bound = self.metric.axis_bound(diff);
+        if best.len() < k || best.peek().is_some_and(|worst| bound <= worst.0) {
+            self.search(far, depth + 1, target, k, best);
+        }
+    }
+}
+
+fn build_kd_tree(points: &[Point3d], order: &mut [usize], depth: usize) {
+    if order.len() <= 1 {
+        return;
+    }
+    let mid = order.len() / 2;
+    let axis = depth % 3;
+    order.select_nth_unstable_by_key(mid, |&index| points[index].coordinate(axis));
+    let (below, above) = order.split_at_mut(mid);
+    build_kd_tree(points, below, depth + 1);
+    build_kd_tree(points, &mut above[1..], depth + 1);
+}
+
+/// Every pair of points, closest first and then by index, without working them all out
+/// up front. Each point's neighbours are fetched from the k-d tree in batches that double in
+/// size, and a heap holds the nearest pair for each point that hasn't been used yet. Each
+/// pair is on the heap once for each of its points, and the copies come out together.
+struct NearestPairs<'a> {
+    tree: KdTree<'a>,
+    neighbours: Vec<Vec<(i128, usize)>>,
+    next_neighbour: Vec<usize>,
+    /// (distance, lower index, higher index, the point whose neighbour this is)
+    heap: BinaryHeap<Reverse<(i128, usize, usize, usize)>>,
+    previous: Option<Connection>,
+}
+
+impl NearestPairs<'_> {
+    const FIRST_BATCH: usize = 8;
+
+    fn new(points: &[Point3d], metric: Metric) -> NearestPairs<'_> {
+        let mut pairs = NearestPairs {
+            tree: KdTree::new(points, metric),
+            neighbours: vec![Vec::new(); points.len()],
+            next_neighbour: vec![0; points.len()],
+            heap: BinaryHeap::new(),
+            previous: None,
+        };
+        for point in 0..points.len() {
+            pairs.queue_next(point);
         }
+        pairs
+    }
+
+    /// Put the next pair for `point` on the heap
+    fn queue_next(&mut self, point: usize) {
+        let next = self.next_neighbour[point];
+        let num_others = self.tree.points.len() - 1;
+        if next >= num_others {
+            return;
+        }
+        if next == self.neighbours[point].len() {
+            let batch = (2 * next).max(Self::FIRST_BATCH).min(num_others);
+            self.neighbours[point] = self.tree.nearest(point, batch);
+        }
+        self.next_neighbour[point] += 1;
+        let (distance, other) = self.neighbours[point][next];
+        let (a, b) = (point.min(other), point.max(other));
+        self.heap.push(Reverse((distance, a, b, point)));
     }
-    next_circuits.push(new_circ);
-    next_circuits
 }
 
-fn point_pairs_sorted_by_distance(lines: &[String]) -> Vec<PointPair> {
-    let coords: Vec<_> = lines.iter().map(parse_line).collect();
+impl Iterator for NearestPairs<'_> {
+    type Item = Connection;
 
-    let mut all_pairs: Vec<(PointPair, f64)> = Vec::new();
-    for i in 0..coords.len() {
-        for j in (i + 1)..coords.len() {
-            let pair = PointPair {
-                a: coords[i],
-                b: coords[j],
+    fn next(&mut self) -> Option<Connection> {
+        loop {
+            let Reverse((distance, a, b, point)) = self.heap.pop()?;
+            self.queue_next(point);
+            let connection = Connection {
+                a,
+                b,
+                distance,
+                metric: self.tree.metric,
             };
-            let dist = distance_between(&coords[i], &coords[j]);
-            all_pairs.push((pair, dist));
+            if self.previous != Some(connection) {
+                self.previous = Some(connection);
+                return Some(connection);
+            }
+        }
+    }
+}
+
+/// Two clusters joined by a connection, making the cluster `id`. Points are the clusters
+/// `0..n`, and each merge makes the next cluster after that.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Merge {
+    id: usize,
+    left: usize,
+    right: usize,
+    size: usize,
+    connection: Connection,
+    /// How many connections were made before this one, including ones within a circuit
+    step: usize,
+}
+
+impl Merge {
+    fn distance(&self) -> f64 {
+        self.connection.length()
+    }
+}
+
+/// Single-linkage clustering of the points, from connecting the closest pairs (Kruskal's
+/// algorithm) until there's one circuit
+struct Clustering {
+    num_points: usize,
+    /// In the order they were made, which is the dendrogram from the bottom up
+    merges: Vec<Merge>,
+}
+
+impl Clustering {
+    fn new(points: &[Point3d], metric: Metric) -> Clustering {
+        let mut circuits = Circuits::new(points.len());
+        // The cluster each circuit's representative point is currently in
+        let mut cluster_ids: Vec<usize> = (0..points.len()).collect();
+        let mut merges = Vec::new();
+        for (step, connection) in NearestPairs::new(points, metric).enumerate() {
+            if circuits.count <= 1 {
+                break;
+            }
+            let (left, right) = (
+                cluster_ids[circuits.find(connection.a)],
+                cluster_ids[circuits.find(connection.b)],
+            );
+            if !circuits.connect(connection.a, connection.b) {
+                continue;
+            }
+            let root = circuits.find(connection.a);
+            let id = points.len() + merges.len();
+            cluster_ids[root] = id;
+            merges.push(Merge {
+                id,
+                left,
+                right,
+                size: circuits.size[root],
+                connection,
+                step,
+            });
+        }
+        Clustering {
+            num_points: points.len(),
+            merges,
+        }
+    }
+
+    /// The minimum spanning tree, shortest edges first
+    fn spanning_tree(&self) -> impl Iterator<Item = Connection> + '_ {
+        self.merges.iter().map(|merge| merge.connection)
+    }
+
+    /// The cluster each point is in after the first `num_connections` connections. Clusters
+    /// are numbered in order of the lowest point in them.
+    fn membership_after(&self, num_connections: usize) -> Vec<usize> {
+        self.membership(|merge| merge.step < num_connections)
+    }
+
+    /// The cluster each point is in when only points at most `max_distance` apart are
+    /// connected. Clusters are numbered in order of the lowest point in them.
+    fn membership_within(&self, max_distance: f64) -> Vec<usize> {
+        self.membership(|merge| merge.distance() <= max_distance)
+    }
+
+    fn membership(&self, include: impl Fn(&Merge) -> bool) -> Vec<usize> {
+        let mut circuits = Circuits::new(self.num_points);
+        for merge in self.merges.iter().take_while(|merge| include(merge)) {
+            circuits.connect(merge.connection.a, merge.connection.b);
+        }
+        circuits.labels()
+    }
+
+    /// The clusters nothing was merged into. Just the last merge once everything's connected.
+    fn roots(&self) -> Vec<usize> {
+        let mut merged = vec![false; self.num_points + self.merges.len()];
+        for merge in &self.merges {
+            merged[merge.left] = true;
+            merged[merge.right] = true;
+        }
+        (0..merged.len()).filter(|&id| !merged[id]).collect()
+    }
+
+    fn to_json(&self, points: &[Point3d], membership: Option<&[usize]>) -> String {
+        let points_json = points
+            .iter()
+            .map(|p| format!("[{},{},{}]", p.x, p.y, p.z))
+            .collect::<Vec<_>>()
+            .join(",");
+        let tree_json = self
+            .spanning_tree()
+            .map(|c| format!(r#"{{"a":{},"b":{},"distance":{}}}"#, c.a, c.b, c.length()))
+            .collect::<Vec<_>>()
+            .join(",");
+        let dendrogram_json = self
+            .merges
+            .iter()
+            .map(|m| {
+                format!(
+                    r#"{{"id":{},"left":{},"right":{},"distance":{},"size":{}}}"#,
+                    m.id,
+                    m.left,
+                    m.right,
+                    m.distance(),
+                    m.size
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let mut json = format!(
+            r#"{{"points":[{points_json}],"spanning_tree":[{tree_json}],"dendrogram":[{dendrogram_json}]"#
+        );
+        if let Some(membership) = membership {
+            let clusters = membership.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+            json.push_str(&format!(r#","clusters":[{}]"#, clusters.join(",")));
         }
+        json.push('}');
+        json
     }
 
-    all_pairs.sort_by(|a, b| a.1.total_cmp(&b.1));
-    all_pairs.iter().map(|(ptp, _)| *ptp).collect()
+    /// The dendrogram as a Newick tree, with points labelled by index and branch lengths from
+    /// the distances clusters were merged at. If not everything is connected, the separate
+    /// trees are joined at the top with no lengths. Written without recursion, as
+    /// single-linkage trees can be as deep as there are points.
+    fn to_newick(&self) -> String {
+        enum Token {
+            Open { id: usize, parent: Option<f64> },
+            Close { id: usize, parent: Option<f64> },
+            Text(&'static str),
+        }
+        let height = |id: usize| match id.checked_sub(self.num_points) {
+            Some(merge) => self.merges[merge].distance(),
+            None => 0.0,
+        };
+        let mut newick = String::new();
+        let roots = self.roots();
+        let mut stack = vec![Token::Text(";")];
+        if roots.len() > 1 {
+            stack.push(Token::Text(")"));
+        }
+        for (i, &id) in roots.iter().enumerate().rev() {
+            stack.push(Token::Open { id, parent: None });
+            if i > 0 {
+                stack.push(Token::Text(","));
+            }
+        }
+        if roots.len() > 1 {
+            stack.push(Token::Text("("));
+        }
+        while let Some(token) = stack.pop() {
+            match token {
+                Token::Text(text) => newick.push_str(text),
+                Token::Open { id, parent } => match id.checked_sub(self.num_points) {
+                    Some(merge) => {
+                        let merge = &self.merges[merge];
+                        let here = Some(merge.distance());
+                        newick.push('(');
+                        stack.push(Token::Close { id, parent });
+                        stack.push(Token::Open {
+                            id: merge.right,
+                            parent: here,
+                        });
+                        stack.push(Token::Text(","));
+                        stack.push(Token::Open {
+                            id: merge.left,
+                            parent: here,
+                        });
+                    }
+                    None => stack.push(Token::Close { id, parent }),
+                },
+                Token::Close { id, parent } => {
+                    if id < self.num_points {
+                        newick.push_str(&id.to_string());
+                    } else {
+                        newick.push(')');
+                    }
+                    if let Some(parent) = parent {
+                        newick.push_str(&format!(":{}", parent - height(id)));
+                    }
+                }
+            }
+        }
+        newick
+    }
+}
+
+/// The points and the connections between them after some number of steps
+struct Snapshot<'a> {
+    points: &'a [Point3d],
+    connections: Vec<Connection>,
+    circuits: Vec<usize>,
+    circuit_sizes: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Plane {
+    XY,
+    XZ,
+    YZ,
 }
 
-fn either_point_in_circuit(pair: &PointPair, circuit: &Circuit) -> bool {
-    circuit.contains(&pair.a) || circuit.contains(&pair.b)
+impl Plane {
+    fn parse(name: &str) -> Result<Plane> {
+        match name.to_ascii_lowercase().as_str() {
+            "xy" => Ok(Plane::XY),
+            "xz" => Ok(Plane::XZ),
+            "yz" => Ok(Plane::YZ),
+            _ => bail!("Unknown plane {name:?}, expected xy, xz or yz"),
+        }
+    }
+
+    fn axes(&self) -> (usize, usize) {
+        match self {
+            Plane::XY => (0, 1),
+            Plane::XZ => (0, 2),
+            Plane::YZ => (1, 2),
+        }
+    }
+
+    fn axis_names(&self) -> (&'static str, &'static str) {
+        match self {
+            Plane::XY => ("x", "y"),
+            Plane::XZ => ("x", "z"),
+            Plane::YZ => ("y", "z"),
+        }
+    }
 }
 
-fn distance_between(a: &Point3d, b: &Point3d) -> f64 {
-    (((a.x - b.x).pow(2) + (a.y - b.y).pow(2) + (a.z - b.z).pow(2)) as f64).sqrt()
+impl<'a> Snapshot<'a> {
+    /// The first `num_connections` connections, as in part a
+    fn after(points: &'a [Point3d], num_connections: usize, metric: Metric) -> Snapshot<'a> {
+        let connections: Vec<Connection> = NearestPairs::new(points, metric)
+            .take(num_connections)
+            .collect();
+        let mut circuits = Circuits::new(points.len());
+        for connection in &connections {
+            circuits.connect(connection.a, connection.b);
+        }
+        let circuits = circuits.labels();
+        let mut circuit_sizes = vec![0; circuits.iter().max().map_or(0, |&c| c + 1)];
+        for &circuit in &circuits {
+            circuit_sizes[circuit] += 1;
+        }
+        Snapshot {
+            points,
+            connections,
+            circuits,
+            circuit_sizes,
+        }
+    }
+
+    /// Points on their own are grey, and each circuit gets its own hue, spread out by the
+    /// golden ratio so neighbouring circuit numbers look different
+    fn colour(&self, point: usize) -> RGBColor {
+        let circuit = self.circuits[point];
+        if self.circuit_sizes[circuit] == 1 {
+            return RGBColor(160, 160, 160);
+        }
+        let hue = (circuit as f64 * 0.618_033_988_75).fract();
+        let (r, g, b) = HSLColor(hue, 0.8, 0.45).rgb();
+        RGBColor(r, g, b)
+    }
+
+    /// An ASCII PLY file with a coloured vertex per point and an edge per connection
+    fn to_ply(&self) -> String {
+        let mut ply = format!(
+            "ply\nformat ascii 1.0\nelement vertex {}\n\
+             property int x\nproperty int y\nproperty int z\n\
+             property uchar red\nproperty uchar green\nproperty uchar blue\n\
+             element edge {}\nproperty int vertex1\nproperty int vertex2\nend_header\n",
+            self.points.len(),
+            self.connections.len()
+        );
+        for (i, p) in self.points.iter().enumerate() {
+            let RGBColor(r, g, b) = self.colour(i);
+            ply.push_str(&format!("{} {} {} {r} {g} {b}\n", p.x, p.y, p.z));
+        }
+        for connection in &self.connections {
+            ply.push_str(&format!("{} {}\n", connection.a, connection.b));
+        }
+        ply
+    }
+
+    /// A Wavefront OBJ file with a line per connection. Vertex colours are the common
+    /// `v x y z r g b` extension, which viewers without it ignore.
+    fn to_obj(&self) -> String {
+        let mut obj = String::new();
+        for (i, p) in self.points.iter().enumerate() {
+            let RGBColor(r, g, b) = self.colour(i);
+            let (r, g, b) = (r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0);
+            obj.push_str(&format!("v {} {} {} {r:.3} {g:.3} {b:.3}\n", p.x, p.y, p.z));
+        }
+        // OBJ counts vertices from 1
+        for connection in &self.connections {
+            obj.push_str(&format!("l {} {}\n", connection.a + 1, connection.b + 1));
+        }
+        obj
+    }
+
+    fn write_projection(&self, path: &Path, plane: Plane) -> Result<()> {
+        let root = BitMapBackend::new(path, (1000, 1000)).into_drawing_area();
+        self.draw_projection(&root, plane)?;
+        root.present()?;
+        Ok(())
+    }
+
+    /// The points flattened onto a plane, with connections drawn in their circuit's colour
+    fn draw_projection<DB: DrawingBackend>(
+        &self,
+        area: &DrawingArea<DB, Shift>,
+        plane: Plane,
+    ) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+        area.fill(&WHITE)?;
+        let (h_axis, v_axis) = plane.axes();
+        let project = |p: &Point3d| (p.coordinate(h_axis), p.coordinate(v_axis));
+        let range = |axis: usize| {
+            let min = self
+                .points
+                .iter()
+                .map(|p| p.coordinate(axis))
+                .min()
+                .unwrap_or(0);
+            let max = self
+                .points
+                .iter()
+                .map(|p| p.coordinate(axis))
+                .max()
+                .unwrap_or(0);
+            min..max.max(min + 1)
+        };
+        let (h_name, v_name) = plane.axis_names();
+        let mut chart = ChartBuilder::on(area)
+            .caption(
+                format!(
+                    "{} connections, {} circuits ({h_name}{v_name})",
+                    self.connections.len(),
+                    self.circuit_sizes.len()
+                ),
+                ("sans-serif", 24),
+            )
+            .margin(10)
+            .x_label_area_size(40)
+            .y_label_area_size(60)
+            .build_cartesian_2d(range(h_axis), range(v_axis))?;
+        chart
+            .configure_mesh()
+            .x_desc(h_name)
+            .y_desc(v_name)
+            .draw()?;
+        chart.draw_series(self.connections.iter().map(|connection| {
+            let points = vec![
+                project(&self.points[connection.a]),
+                project(&self.points[connection.b]),
+            ];
+            PathElement::new(points, self.colour(connection.a))
+        }))?;
+        chart.draw_series(
+            self.points
+                .iter()
+                .enumerate()
+                .map(|(i, p)| Circle::new(project(p), 3, self.colour(i).filled())),
+        )?;
+        Ok(())
+    }
 }
 
-fn parse_line(line: &String) -> Point3d {
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let arg_value = |flag: &str| args.iter().skip_while(|arg| *arg != flag).nth(1);
+    let lines = load_input(DAY, Input::Puzzle);
+    let a = solve_part_a(&lines, 1000);
+    println!("Solution to a: {a}");
+    let b = solve_part_b(&lines);
+    println!("Solution to b: {b}");
+
+    let metric = Metric::parse(arg_value("--metric").map_or("euclidean", |m| m.as_str()))
+        .expect("Couldn't parse metric");
+    let ply = arg_value("--ply");
+    let obj = arg_value("--obj");
+    let png = arg_value("--png");
+    if ply.is_some() || obj.is_some() || png.is_some() {
+        let points: Vec<Point3d> = lines.iter().map(|line| parse_line(line)).collect();
+        let step = arg_value("--step").map_or(1000, |n| n.parse().expect("Couldn't parse step"));
+        let snapshot = Snapshot::after(&points, step, metric);
+        if let Some(path) = ply {
+            std::fs::write(path, snapshot.to_ply()).expect("Couldn't write PLY");
+        }
+        if let Some(path) = obj {
+            std::fs::write(path, snapshot.to_obj()).expect("Couldn't write OBJ");
+        }
+        if let Some(path) = png {
+            let plane = Plane::parse(arg_value("--plane").map_or("xy", |p| p.as_str()))
+                .expect("Couldn't parse plane");
+            snapshot
+                .write_projection(Path::new(path), plane)
+                .expect("Couldn't write projection");
+        }
+    }
+
+    let json = arg_value("--json");
+    let newick = arg_value("--newick");
+    let connections = arg_value("--connections").map(|n| {
+        n.parse::<usize>()
+            .expect("Couldn't parse number of connections")
+    });
+    let cutoff = arg_value("--cutoff").map(|d| d.parse::<f64>().expect("Couldn't parse cutoff"));
+    if json.is_none() && newick.is_none() && connections.is_none() && cutoff.is_none() {
+        return;
+    }
+    let points: Vec<Point3d> = lines.iter().map(|line| parse_line(line)).collect();
+    let clustering = Clustering::new(&points, metric);
+    let membership = match (connections, cutoff) {
+        (Some(connections), _) => Some(clustering.membership_after(connections)),
+        (None, Some(cutoff)) => Some(clustering.membership_within(cutoff)),
+        (None, None) => None,
+    };
+    if let Some(membership) = &membership {
+        let num_clusters = membership.iter().max().map_or(0, |&c| c + 1);
+        let mut sizes = vec![0; num_clusters];
+        for &cluster in membership {
+            sizes[cluster] += 1;
+        }
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes.truncate(10);
+        println!("{num_clusters} clusters, the largest with sizes {sizes:?}");
+    }
+    if let Some(path) = json {
+        std::fs::write(path, clustering.to_json(&points, membership.as_deref()))
+            .expect("Couldn't write JSON");
+    }
+    if let Some(path) = newick {
+        std::fs::write(path, clustering.to_newick()).expect("Couldn't write Newick tree");
+    }
+}
+
+fn solve_part_a(lines: &[String], num_connection_to_make: usize) -> usize {
+    let points: Vec<Point3d> = lines.iter().map(|line| parse_line(line)).collect();
+    let mut circuits = Circuits::new(points.len());
+    let mut pairs = NearestPairs::new(&points, Metric::SquaredEuclidean);
+    for _ in 0..num_connection_to_make {
+        let next_connection = pairs.next().expect("No more connections available");
+        circuits.connect(next_connection.a, next_connection.b);
+    }
+    let mut sizes = circuits.sizes();
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    sizes.iter().take(3).product()
+}
+
+fn solve_part_b(lines: &[String]) -> i64 {
+    let points: Vec<Point3d> = lines.iter().map(|line| parse_line(line)).collect();
+    let connection = Clustering::new(&points, Metric::SquaredEuclidean)
+        .spanning_tree()
+        .last()
+        .expect("Didn't make any connnections");
+    points[connection.a].x * points[connection.b].x
+}
+
+fn parse_line(line: &str) -> Point3d {
     let coords: Vec<u32> = line
         .split(',')
         .map(|c| c.parse().expect("Couldn't parse char as number"))
@@ -130,6 +804,156 @@ fn parse_line(line: &String) -> Point3d {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc25::PseudoRandom;
+    use rstest::rstest;
+
+    fn random_points(num_points: usize, max: u64, seed: u64) -> Vec<Point3d> {
+        let mut random = PseudoRandom::new(seed);
+        let mut next = || random.below(max) as i64;
+        (0..num_points)
+            .map(|_| Point3d {
+                x: next(),
+                y: next(),
+                z: next(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_circuits() {
+        let mut circuits = Circuits::new(5);
+        assert!(circuits.connect(0, 1));
+        assert!(circuits.connect(3, 4));
+        assert!(circuits.connect(1, 4));
+        assert!(!circuits.connect(0, 3));
+        assert_eq!(circuits.count, 2);
+        let mut sizes = circuits.sizes();
+        sizes.sort_unstable();
+        assert_eq!(sizes, [1, 4]);
+        assert_eq!(circuits.find(0), circuits.find(3));
+        assert_ne!(circuits.find(0), circuits.find(2));
+    }
+
+    #[rstest]
+    fn test_nearest_pairs_match_sorting_every_pair(
+        #[values(Metric::SquaredEuclidean, Metric::Manhattan, Metric::Chebyshev)] metric: Metric,
+    ) {
+        // A small range of coordinates, so there are plenty of ties and repeated points
+        for (num_points, max) in [(0, 10), (1, 10), (2, 10), (60, 6), (200, 1000)] {
+            let points = random_points(num_points, max, num_points as u64);
+            let mut expected = Vec::new();
+            for a in 0..points.len() {
+                for b in (a + 1)..points.len() {
+                    expected.push((metric.distance(&points[a], &points[b]), a, b));
+                }
+            }
+            expected.sort();
+            let pairs: Vec<_> = NearestPairs::new(&points, metric)
+                .map(|c| (c.distance, c.a, c.b))
+                .collect();
+            assert_eq!(pairs, expected);
+        }
+    }
+
+    #[test]
+    fn test_metrics() {
+        let a = Point3d { x: 0, y: 0, z: 0 };
+        let b = Point3d { x: 3, y: -4, z: 12 };
+        assert_eq!(Metric::SquaredEuclidean.distance(&a, &b), 169);
+        assert_eq!(Metric::SquaredEuclidean.length(169), 13.0);
+        assert_eq!(Metric::Manhattan.distance(&a, &b), 19);
+        assert_eq!(Metric::Chebyshev.distance(&a, &b), 12);
+        // Too big to square in an i64
+        let far = Point3d {
+            x: u32::MAX as i64,
+            y: u32::MAX as i64,
+            z: u32::MAX as i64,
+        };
+        assert_eq!(
+            Metric::SquaredEuclidean.distance(&a, &far),
+            3 * (u32::MAX as i128).pow(2)
+        );
+        assert_eq!(Metric::parse("manhattan").unwrap(), Metric::Manhattan);
+        assert!(Metric::parse("cosine").is_err());
+    }
+
+    fn points(coords: &[(i64, i64, i64)]) -> Vec<Point3d> {
+        coords
+            .iter()
+            .map(|&(x, y, z)| Point3d { x, y, z })
+            .collect()
+    }
+
+    #[test]
+    fn test_clustering() {
+        // Two pairs far apart, and a point off on its own
+        let points = points(&[(0, 0, 0), (100, 0, 0), (3, 4, 0), (100, 0, 5), (0, 50, 0)]);
+        let clustering = Clustering::new(&points, Metric::SquaredEuclidean);
+        let tree: Vec<_> = clustering.spanning_tree().map(|c| (c.a, c.b)).collect();
+        assert_eq!(tree, [(0, 2), (1, 3), (2, 4), (1, 2)]);
+        assert_eq!(clustering.merges[2].id, 7);
+        assert_eq!(clustering.merges[2].left, 5);
+        assert_eq!(clustering.merges[2].right, 4);
+        assert_eq!(clustering.merges[3].size, 5);
+        assert_eq!(clustering.roots(), [8]);
+        assert_eq!(clustering.membership_after(1), [0, 1, 0, 2, 3]);
+        assert_eq!(clustering.membership_within(5.0), [0, 1, 0, 1, 2]);
+        assert_eq!(clustering.membership_within(1000.0), [0; 5]);
+        let newick = clustering.to_newick();
+        let (near, far) = (2125.0_f64.sqrt(), 9425.0_f64.sqrt());
+        let expected = format!(
+            "((1:5,3:5):{},((0:5,2:5):{},4:{near}):{});",
+            far - 5.0,
+            near - 5.0,
+            far - near
+        );
+        assert_eq!(newick, expected);
+    }
+
+    #[test]
+    fn test_clustering_exports() {
+        let points = points(&[(0, 0, 0), (3, 4, 0)]);
+        let clustering = Clustering::new(&points, Metric::SquaredEuclidean);
+        assert_eq!(
+            clustering.to_json(&points, Some(&[0, 0])),
+            r#"{"points":[[0,0,0],[3,4,0]],"spanning_tree":[{"a":0,"b":1,"distance":5}],"dendrogram":[{"id":2,"left":0,"right":1,"distance":5,"size":2}],"clusters":[0,0]}"#
+        );
+        assert_eq!(
+            Clustering::new(&points[..1], Metric::SquaredEuclidean).to_newick(),
+            "0;"
+        );
+        assert_eq!(
+            Clustering::new(&[], Metric::SquaredEuclidean).to_newick(),
+            ";"
+        );
+    }
+
+    #[test]
+    fn test_snapshot_exports() {
+        let points = points(&[(0, 0, 0), (3, 4, 0), (100, 0, 0)]);
+        let snapshot = Snapshot::after(&points, 1, Metric::SquaredEuclidean);
+        assert_eq!(snapshot.circuits, [0, 0, 1]);
+        let ply = snapshot.to_ply();
+        assert!(ply.starts_with("ply\nformat ascii 1.0\nelement vertex 3\n"));
+        assert!(ply.contains("element edge 1\n"));
+        let body: Vec<&str> = ply.lines().skip_while(|l| *l != "end_header").collect();
+        assert_eq!(
+            body,
+            [
+                "end_header",
+                "0 0 0 207 23 23",
+                "3 4 0 207 23 23",
+                "100 0 0 160 160 160",
+                "0 1"
+            ]
+        );
+        let obj = snapshot.to_obj();
+        assert_eq!(obj.lines().filter(|l| l.starts_with("v ")).count(), 3);
+        assert!(obj.starts_with("v 0 0 0 0.812 0.090 0.090\n"));
+        assert!(obj.ends_with("l 1 2\n"));
+        assert_eq!(Plane::parse("XZ").unwrap(), Plane::XZ);
+        assert!(Plane::parse("xx").is_err());
+    }
 
     #[test]
     fn test_part_a() {
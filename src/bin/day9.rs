@@ -1,6 +1,9 @@
+use anyhow::{Result, ensure};
 use aoc25::{Input, load_input};
 use itertools::Itertools;
+use plotters::coord::Shift;
 use plotters::prelude::*;
+use std::path::Path;
 
 const DAY: u8 = 9;
 
@@ -10,265 +13,285 @@ struct Point {
     y: u32,
 }
 
-#[derive(PartialEq, Eq, Debug)]
-enum CornerType {
-    TopLeft,
-    TopRight,
-    BottomRight,
-    BottomLeft,
+/// A rectangle with red tiles at opposite corners
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Rectangle {
+    a: Point,
+    b: Point,
 }
 
-#[derive(Debug)]
-struct Corner {
-    pt: Point,
-    compatible_as: Vec<CornerType>,
+impl Rectangle {
+    fn area(&self) -> u64 {
+        square_size(self.a, self.b)
+    }
 }
 
-fn main() {
-    let lines = load_input(DAY, Input::Puzzle);
-    plot(&lines);
-    let a = solve_part_a(&lines);
-    println!("Solution to a: {a}");
-    let b = solve_part_b(&lines);
-    println!("Solution to b: {b}");
+/// Which tiles are inside the polygon, counting its edges, on a compressed grid. Each
+/// compressed column is either a red tile's x, or the whole gap between two neighbouring
+/// ones, and the same for rows. Every tile in a compressed cell is on the same side of the
+/// edges, so the cells can stand in for the tiles. Gaps get a cell even when there are no
+/// tiles in them, so that the outside can get between edges on neighbouring rows or columns.
+/// There's an extra cell of outside all the way round.
+struct CompressedPolygon {
+    xs: CompressedAxis,
+    ys: CompressedAxis,
+    /// How many outside cells with tiles in them there are above and left of each cell, not
+    /// counting its own row and column
+    outside_before: Vec<Vec<u32>>,
 }
 
-fn plot(lines: &[String]) {
-    let root_area = BitMapBackend::new("images/day9_plot.png", (600, 400)).into_drawing_area();
-    root_area.fill(&WHITE).unwrap();
-    let points: Vec<_> = lines.iter().map(parse_line).collect();
-
-    let mut ctx = ChartBuilder::on(&root_area)
-        .set_label_area_size(LabelAreaPosition::Left, 40)
-        .set_label_area_size(LabelAreaPosition::Bottom, 40)
-        .caption("Day 9 points", ("sans-serif", 40))
-        .build_cartesian_2d(0..100_000, 0..100_000)
-        .unwrap();
-    ctx.configure_mesh().draw().unwrap();
-    ctx.draw_series(
-        points
-            .iter()
-            .map(|pt| TriangleMarker::new((pt.x as i32, pt.y as i32), 5, &BLUE)),
-    )
-    .unwrap();
-}
+impl CompressedPolygon {
+    fn new(corners: &[Point]) -> Result<CompressedPolygon> {
+        let xs = CompressedAxis::new(corners.iter().map(|pt| pt.x));
+        let ys = CompressedAxis::new(corners.iter().map(|pt| pt.y));
+        let (width, height) = (xs.len(), ys.len());
+        let mut on_edge = vec![vec![false; width]; height];
+        for (from, to) in corners.iter().circular_tuple_windows() {
+            ensure!(
+                from.x == to.x || from.y == to.y,
+                "Edge from {from:?} to {to:?} isn't horizontal or vertical"
+            );
+            let (x1, x2) = (xs.cell(from.x), xs.cell(to.x));
+            let (y1, y2) = (ys.cell(from.y), ys.cell(to.y));
+            for row in on_edge.iter_mut().take(y1.max(y2) + 1).skip(y1.min(y2)) {
+                row[x1.min(x2)..=x1.max(x2)].fill(true);
+            }
+        }
 
-fn solve_part_a(lines: &[String]) -> u64 {
-    let all_points = lines.iter().map(parse_line).collect();
-    sorted_sizes(&all_points)[0]
-}
+        // Everything that can be reached from the border without crossing an edge is outside
+        let mut outside = vec![vec![false; width]; height];
+        let mut to_visit: Vec<(usize, usize)> = vec![(0, 0)];
+        outside[0][0] = true;
+        while let Some((x, y)) = to_visit.pop() {
+            let neighbours = [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ];
+            for (nx, ny) in neighbours {
+                if nx < width && ny < height && !on_edge[ny][nx] && !outside[ny][nx] {
+                    outside[ny][nx] = true;
+                    to_visit.push((nx, ny));
+                }
+            }
+        }
 
-fn solve_part_b(lines: &[String]) -> u64 {
-    let all_corner_points = lines.iter().map(parse_line).collect();
-    let all_points = boundary_points(&all_corner_points, 100);
-    let all_corners = to_corners(&all_corner_points);
-    let sorted_candidates = sorted_sizes_corners(&all_corners);
-    for (candidate_square, size) in sorted_candidates {
-        if !all_points
-            .iter()
-            .any(|pt| square_contains(pt, &(candidate_square.0.pt, candidate_square.1.pt)))
-        {
-            return size;
+        // Only count cells with tiles in, as a rectangle can span an empty gap that's outside
+        let mut outside_before = vec![vec![0; width + 1]; height + 1];
+        for y in 0..height {
+            for x in 0..width {
+                let counted = outside[y][x] && xs.holds_tiles(x) && ys.holds_tiles(y);
+                outside_before[y + 1][x + 1] = outside_before[y][x + 1] + outside_before[y + 1][x]
+                    - outside_before[y][x]
+                    + counted as u32;
+            }
         }
+        Ok(CompressedPolygon {
+            xs,
+            ys,
+            outside_before,
+        })
     }
-    panic!("Couldn't find any solution to part b")
-}
 
-fn boundary_points(corner_points: &Vec<Point>, step_size: usize) -> Vec<Point> {
-    corner_points
-        .iter()
-        .chain(corner_points.iter().take(1))
-        .tuple_windows()
-        .map(|points| points_between(points, step_size))
-        .flatten()
-        .unique()
-        .collect()
+    /// Whether every tile in the rectangle is inside the polygon or on its edge
+    fn contains(&self, rectangle: &Rectangle) -> bool {
+        let (x1, x2) = (self.xs.cell(rectangle.a.x), self.xs.cell(rectangle.b.x));
+        let (y1, y2) = (self.ys.cell(rectangle.a.y), self.ys.cell(rectangle.b.y));
+        let (left, right) = (x1.min(x2), x1.max(x2) + 1);
+        let (top, bottom) = (y1.min(y2), y1.max(y2) + 1);
+        let outside = self.outside_before[bottom][right] + self.outside_before[top][left]
+            - self.outside_before[top][right]
+            - self.outside_before[bottom][left];
+        outside == 0
+    }
 }
 
-fn points_between(points: (&Point, &Point), step_size: usize) -> Vec<Point> {
-    let mut result: Vec<Point> = if points.0.x == points.1.x {
-        let from = points.0.y.min(points.1.y);
-        let to = points.0.y.max(points.1.y);
-        (from..to)
-            .step_by(step_size)
-            .map(|y| Point { x: points.0.x, y })
-            .collect()
-    } else {
-        let from = points.0.x.min(points.1.x);
-        let to = points.0.x.max(points.1.x);
-        (from..to)
-            .step_by(step_size)
-            .map(|x| Point { x, y: points.0.y })
-            .collect()
-    };
+/// The red tiles' coordinates along one axis. Compressed, each coordinate is an odd cell, and
+/// the gaps between them and the border at each end are the even cells.
+struct CompressedAxis {
+    coords: Vec<u32>,
+}
 
-    // Add points.0 if it's not already first
-    if result.first() != Some(points.0) {
-        result.insert(0, *points.0);
+impl CompressedAxis {
+    fn new(coords: impl Iterator<Item = u32>) -> CompressedAxis {
+        CompressedAxis {
+            coords: coords.sorted().dedup().collect(),
+        }
     }
 
-    result
-}
+    /// The number of compressed cells, including the border at each end
+    fn len(&self) -> usize {
+        2 * self.coords.len() + 1
+    }
 
-fn to_corners(points: &Vec<Point>) -> Vec<Corner> {
-    let mut padded_pts = points.clone();
-    padded_pts.insert(0, *points.last().unwrap());
-    padded_pts.push(points[0]);
+    /// The compressed cell of a red tile's coordinate
+    fn cell(&self, coord: u32) -> usize {
+        let index = self
+            .coords
+            .binary_search(&coord)
+            .expect("Only red tile coordinates can be compressed");
+        2 * index + 1
+    }
 
-    padded_pts
-        .iter()
-        .tuple_windows()
-        .map(|(before, pt, after)| create_corner(before, pt, after))
-        .collect()
+    /// Whether there are any tiles in a compressed cell. Neighbouring coordinates have an
+    /// empty gap between them.
+    fn holds_tiles(&self, cell: usize) -> bool {
+        if cell % 2 == 1 {
+            return true;
+        }
+        match (cell / 2).checked_sub(1) {
+            Some(before) if before + 1 < self.coords.len() => {
+                self.coords[before + 1] > self.coords[before] + 1
+            }
+            // The border
+            _ => false,
+        }
+    }
 }
 
-fn create_corner(before: &Point, centre: &Point, after: &Point) -> Corner {
-    Corner {
-        pt: *centre,
-        compatible_as: get_corner_type(before, centre, after),
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let arg_value = |flag: &str| args.iter().skip_while(|arg| *arg != flag).nth(1);
+    let lines = load_input(DAY, Input::Puzzle);
+    let a = solve_part_a(&lines);
+    println!("Solution to a: {a}");
+    let b = solve_part_b(&lines);
+    println!("Solution to b: {b}");
+
+    if let Some(path) = arg_value("--visualise") {
+        let corners: Vec<Point> = lines.iter().map(|line| parse_line(line)).collect();
+        visualise(Path::new(path), &corners).expect("Couldn't write visualisation");
     }
 }
 
-fn get_corner_type(before: &Point, centre: &Point, after: &Point) -> Vec<CornerType> {
-    // Illustration of all 8 corner types. Corners going clockwise.
-    // (x,y) = (0,0) is the top left corner.
-    // ..###..
-    // ..###..
-    // ..###..
-    // #######
-    // #######
-    // #######
-    // ..###..
-    // ..###..
-    // ..###..
-    if before.x == centre.x {
-        // Vertical
-        if before.y < centre.y {
-            // Going up
-            if after.x > centre.x {
-                // Up -> Right
-                return vec![CornerType::TopLeft];
-            } else {
-                // Up -> Left
-                return vec![
-                    CornerType::BottomRight,
-                    CornerType::BottomLeft,
-                    CornerType::TopLeft,
-                ];
-            }
-        } else {
-            // Going down
-            if after.x > centre.x {
-                // Down -> Right
-                return vec![
-                    CornerType::TopRight,
-                    CornerType::TopLeft,
-                    CornerType::BottomRight,
-                ];
-            } else {
-                // Down -> Left
-                return vec![CornerType::BottomRight];
-            }
-        }
-    } else if before.y == centre.y {
-        // Horizontal
-        if before.x < centre.x {
-            // Going right
-            if after.y > centre.y {
-                // Right -> Down
-                return vec![CornerType::TopRight];
-            } else {
-                // Right -> Up
-                return vec![
-                    CornerType::TopRight,
-                    CornerType::BottomLeft,
-                    CornerType::TopLeft,
-                ];
-            }
-        } else {
-            // Going left
-            if after.y > centre.y {
-                // Left -> Down
-                return vec![
-                    CornerType::TopRight,
-                    CornerType::BottomRight,
-                    CornerType::BottomLeft,
-                ];
-            } else {
-                // Left -> Up
-                return vec![CornerType::BottomLeft];
-            }
-        }
+/// Draws the polygon with the best rectangles for both parts, as an SVG if the path ends in
+/// `.svg` and a PNG otherwise
+fn visualise(path: &Path, corners: &[Point]) -> Result<()> {
+    let size = (1000, 1000);
+    let best = [
+        largest_rectangle(corners),
+        largest_rectangle_inside(corners)?,
+    ];
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"))
+    {
+        let root = SVGBackend::new(path, size).into_drawing_area();
+        draw_floor(&root, corners, best)?;
+        root.present()?;
     } else {
-        panic!("Before {before:?} doesn't line up with centre {centre:?}")
+        let root = BitMapBackend::new(path, size).into_drawing_area();
+        draw_floor(&root, corners, best)?;
+        root.present()?;
     }
+    Ok(())
 }
 
-fn compatible_corners(a: &Corner, b: &Corner) -> bool {
-    for type_a_corner in a.compatible_as.iter() {
-        if b.compatible_as
-            .iter()
-            .contains(&opposite_corner_type(&type_a_corner))
-        {
-            // Check corner positions are consistent (e.g. Bottom Left can't be above Top Right)
-            return match type_a_corner {
-                CornerType::TopLeft => a.pt.x <= b.pt.x && a.pt.y <= b.pt.y,
-                CornerType::BottomLeft => a.pt.x <= b.pt.x && a.pt.y >= b.pt.y,
-                CornerType::BottomRight => a.pt.x >= b.pt.x && a.pt.y >= b.pt.y,
-                CornerType::TopRight => a.pt.x >= b.pt.x && a.pt.y <= b.pt.y,
-            };
+/// The polygon's outline and red tiles, with the largest rectangle for part a in blue and the
+/// largest one inside the polygon for part b in green
+fn draw_floor<DB: DrawingBackend>(
+    area: &DrawingArea<DB, Shift>,
+    corners: &[Point],
+    [part_a, part_b]: [Option<Rectangle>; 2],
+) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    area.fill(&WHITE)?;
+    let range = |coords: Vec<u32>| {
+        let min = coords.iter().copied().min().unwrap_or(0);
+        let max = coords.iter().copied().max().unwrap_or(0);
+        let margin = ((max - min) / 20).max(1);
+        min.saturating_sub(margin)..max.saturating_add(margin)
+    };
+    let mut chart = ChartBuilder::on(area)
+        .caption("Day 9 tiles", ("sans-serif", 24))
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(
+            range(corners.iter().map(|pt| pt.x).collect()),
+            range(corners.iter().map(|pt| pt.y).collect()),
+        )?;
+    chart.configure_mesh().draw()?;
+
+    let best = [(part_a, BLUE, "Part a"), (part_b, GREEN, "Part b")];
+    for (rectangle, colour, label) in best {
+        if let Some(Rectangle { a, b }) = rectangle {
+            chart
+                .draw_series([plotters::element::Rectangle::new(
+                    [(a.x, a.y), (b.x, b.y)],
+                    colour.mix(0.3).filled(),
+                )])?
+                .label(label)
+                .legend(move |(x, y)| {
+                    plotters::element::Rectangle::new(
+                        [(x, y - 5), (x + 10, y + 5)],
+                        colour.filled(),
+                    )
+                });
         }
     }
-    false
-}
 
-fn opposite_corner_type(corner: &CornerType) -> CornerType {
-    match corner {
-        CornerType::TopLeft => CornerType::BottomRight,
-        CornerType::BottomLeft => CornerType::TopRight,
-        CornerType::BottomRight => CornerType::TopLeft,
-        CornerType::TopRight => CornerType::BottomLeft,
-    }
+    let outline = corners
+        .iter()
+        .chain(corners.first())
+        .map(|pt| (pt.x, pt.y))
+        .collect::<Vec<_>>();
+    chart.draw_series([PathElement::new(outline, BLACK)])?;
+    chart.draw_series(
+        corners
+            .iter()
+            .map(|pt| Circle::new((pt.x, pt.y), 3, RED.filled())),
+    )?;
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    Ok(())
 }
 
-fn square_contains(point: &Point, square: &(Point, Point)) -> bool {
-    range_containts(point.x, (square.0.x, square.1.x))
-        && range_containts(point.y, (square.0.y, square.1.y))
+fn solve_part_a(lines: &[String]) -> u64 {
+    let all_points: Vec<Point> = lines.iter().map(|line| parse_line(line)).collect();
+    largest_rectangle(&all_points)
+        .expect("Couldn't find any solution to part a")
+        .area()
 }
 
-fn range_containts(test: u32, range: (u32, u32)) -> bool {
-    let upper = range.0.max(range.1);
-    let lower = range.0.min(range.1);
-    test < upper && test > lower
+fn solve_part_b(lines: &[String]) -> u64 {
+    let corners: Vec<Point> = lines.iter().map(|line| parse_line(line)).collect();
+    largest_rectangle_inside(&corners)
+        .expect("Couldn't make a polygon from the red tiles")
+        .expect("Couldn't find any solution to part b")
+        .area()
 }
 
-fn sorted_sizes_corners(points: &Vec<Corner>) -> Vec<((&Corner, &Corner), u64)> {
-    points
+/// The biggest rectangle with red corners that's entirely inside the polygon
+fn largest_rectangle_inside(corners: &[Point]) -> Result<Option<Rectangle>> {
+    let polygon = CompressedPolygon::new(corners)?;
+    Ok(corners
         .iter()
-        .combinations(2)
-        .map(|vec| ((vec[0], vec[1]), square_size(vec[0].pt, vec[1].pt)))
-        .sorted_by(|(_, sizea), (_, sizeb)| sizeb.cmp(sizea))
-        .filter(|((corner_a, corner_b), _)| compatible_corners(corner_a, corner_b))
-        .collect()
+        .tuple_combinations()
+        .map(|(&a, &b)| Rectangle { a, b })
+        .filter(|rectangle| polygon.contains(rectangle))
+        .max_by_key(|rectangle| rectangle.area()))
 }
 
-fn sorted_sizes(points: &Vec<Point>) -> Vec<u64> {
-    points
+/// The biggest rectangle with red corners, ignoring the polygon
+fn largest_rectangle(corners: &[Point]) -> Option<Rectangle> {
+    corners
         .iter()
-        .combinations(2)
-        .map(|vec| square_size(*vec[0], *vec[1]))
-        .sorted()
-        .rev()
-        .collect()
+        .tuple_combinations()
+        .map(|(&a, &b)| Rectangle { a, b })
+        .max_by_key(|rectangle| rectangle.area())
 }
 
 fn square_size(a: Point, b: Point) -> u64 {
     // Square size is inclusive of both start and end points so add one to each length
-    ((a.x as i64 - b.x as i64 + 1).abs() * (a.y as i64 - b.y as i64 + 1).abs()) as u64
+    (a.x.abs_diff(b.x) as u64 + 1) * (a.y.abs_diff(b.y) as u64 + 1)
 }
 
-fn parse_line(line: &String) -> Point {
-    let parts: Vec<_> = line.split(",").collect();
+fn parse_line(line: &str) -> Point {
+    let parts: Vec<_> = line.split(',').collect();
     Point {
         x: parts[0].parse().expect("Couldn't parse"),
         y: parts[1].parse().expect("Couldn't parse"),
@@ -279,6 +302,185 @@ fn parse_line(line: &String) -> Point {
 mod tests {
     use super::*;
 
+    /// Whether a tile is inside the polygon or on its edge, by casting a ray to the right
+    fn brute_force_inside(corners: &[Point], x: u32, y: u32) -> bool {
+        let mut crossings = 0;
+        for (from, to) in corners.iter().circular_tuple_windows() {
+            let (x1, x2) = (from.x.min(to.x), from.x.max(to.x));
+            let (y1, y2) = (from.y.min(to.y), from.y.max(to.y));
+            if (x1..=x2).contains(&x) && (y1..=y2).contains(&y) {
+                return true;
+            }
+            if from.x == to.x && from.x > x && (y1..y2).contains(&y) {
+                crossings += 1;
+            }
+        }
+        crossings % 2 == 1
+    }
+
+    fn brute_force_largest(corners: &[Point]) -> u64 {
+        corners
+            .iter()
+            .tuple_combinations()
+            .filter(|(a, b)| {
+                (a.x.min(b.x)..=a.x.max(b.x)).all(|x| {
+                    (a.y.min(b.y)..=a.y.max(b.y)).all(|y| brute_force_inside(corners, x, y))
+                })
+            })
+            .map(|(a, b)| square_size(*a, *b))
+            .max()
+            .unwrap()
+    }
+
+    fn points(coords: &[(u32, u32)]) -> Vec<Point> {
+        coords.iter().map(|&(x, y)| Point { x, y }).collect()
+    }
+
+    #[test]
+    fn test_thin_intrusion() {
+        // A square with a slot cut down into it, one tile wide
+        let corners = points(&[
+            (0, 0),
+            (10, 0),
+            (10, 10),
+            (7, 10),
+            (7, 1),
+            (5, 1),
+            (5, 10),
+            (0, 10),
+        ]);
+        let polygon = CompressedPolygon::new(&corners).unwrap();
+        let whole = Rectangle {
+            a: corners[0],
+            b: corners[2],
+        };
+        assert!(!polygon.contains(&whole));
+        let best = largest_rectangle_inside(&corners).unwrap().unwrap();
+        assert_eq!(best.area(), 66);
+        assert_eq!(best.area(), brute_force_largest(&corners));
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let shapes = [
+            vec![
+                (7, 1),
+                (11, 1),
+                (11, 7),
+                (9, 7),
+                (9, 5),
+                (2, 5),
+                (2, 3),
+                (7, 3),
+            ],
+            vec![
+                (1, 1),
+                (8, 1),
+                (8, 4),
+                (6, 4),
+                (6, 2),
+                (3, 2),
+                (3, 6),
+                (1, 6),
+            ],
+            // A slot with no tiles in it, so everything's inside
+            vec![
+                (0, 0),
+                (10, 0),
+                (10, 10),
+                (6, 10),
+                (6, 1),
+                (5, 1),
+                (5, 10),
+                (0, 10),
+            ],
+            vec![
+                (0, 0),
+                (3, 0),
+                (3, 1),
+                (4, 1),
+                (4, 0),
+                (6, 0),
+                (6, 2),
+                (0, 2),
+            ],
+            vec![
+                (0, 0),
+                (4, 0),
+                (4, 2),
+                (6, 2),
+                (6, 0),
+                (9, 0),
+                (9, 5),
+                (0, 5),
+            ],
+        ];
+        for shape in shapes {
+            let corners = points(&shape);
+            assert_eq!(
+                largest_rectangle_inside(&corners).unwrap().unwrap().area(),
+                brute_force_largest(&corners),
+                "{shape:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_diagonal_edge() {
+        let corners = points(&[(0, 0), (5, 0), (5, 5), (2, 3)]);
+        let err = largest_rectangle_inside(&corners).unwrap_err();
+        assert!(
+            err.to_string().contains("isn't horizontal or vertical"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_draw_floor() {
+        let corners = points(&[
+            (7, 1),
+            (11, 1),
+            (11, 7),
+            (9, 7),
+            (9, 5),
+            (2, 5),
+            (2, 3),
+            (7, 3),
+        ]);
+        let mut svg = String::new();
+        {
+            let root = SVGBackend::with_string(&mut svg, (400, 400)).into_drawing_area();
+            let best = [
+                largest_rectangle(&corners),
+                largest_rectangle_inside(&corners).unwrap(),
+            ];
+            draw_floor(&root, &corners, best).unwrap();
+            root.present().unwrap();
+        }
+        assert!(svg.contains("Part a"));
+        assert!(svg.contains("Part b"));
+        assert_eq!(svg.matches("<circle").count(), corners.len());
+
+        // Coordinates near the top of the range still fit on the axes
+        let corners = points(&[
+            (u32::MAX - 10, u32::MAX - 10),
+            (u32::MAX, u32::MAX - 10),
+            (u32::MAX, u32::MAX),
+            (u32::MAX - 10, u32::MAX),
+        ]);
+        let mut svg = String::new();
+        {
+            let root = SVGBackend::with_string(&mut svg, (400, 400)).into_drawing_area();
+            let best = [
+                largest_rectangle(&corners),
+                largest_rectangle_inside(&corners).unwrap(),
+            ];
+            draw_floor(&root, &corners, best).unwrap();
+            root.present().unwrap();
+        }
+        assert_eq!(svg.matches("<circle").count(), corners.len());
+    }
+
     #[test]
     fn test_part_a() {
         assert_eq!(50, solve_part_a(&load_input(DAY, Input::Test)));
@@ -31,3 +31,29 @@ where
     let file = fs::File::open(path).expect("No file found at path");
     BufReader::new(file).lines()
 }
+
+/// A small, seeded pseudo-random number generator (a 64-bit LCG), so that tests can build
+/// the same random inputs every run
+pub struct PseudoRandom {
+    state: u64,
+}
+
+impl PseudoRandom {
+    pub fn new(seed: u64) -> PseudoRandom {
+        PseudoRandom { state: seed }
+    }
+
+    /// The next 64 bits. The low bits are the least random, so shift them away before use.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self
+            .state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.state
+    }
+
+    /// A number below `max`, taken from the high bits
+    pub fn below(&mut self, max: u64) -> u64 {
+        (self.next_u64() >> 33) % max
+    }
+}